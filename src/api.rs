@@ -0,0 +1,131 @@
+use crate::{config::ApiConfig, logger::Logger, scores::Score, scrobbler::ScrobblerAction, utils::strip_ansi_codes};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::{
+    collections::BTreeMap,
+    sync::{LazyLock, Mutex, mpsc::Sender},
+    thread::spawn,
+    time::Duration,
+};
+use tiny_http::{Header, Method, Response, Server};
+
+const ACTION_HEADER: &str = "X-Osu-Scrobbler";
+
+static STATUS: LazyLock<Mutex<ApiStatus>> = LazyLock::new(|| Mutex::new(ApiStatus::default()));
+
+#[derive(Serialize, Default)]
+pub struct ApiStatus {
+    pub paused: bool,
+    pub osu_running: bool,
+    pub last_score: Option<Value>,
+    pub last_scrobbles: BTreeMap<&'static str, Value>,
+    pub last_error: Option<Value>,
+    pub config: Value,
+}
+
+pub struct Api;
+
+impl Api {
+    pub fn update_status<F: FnOnce(&mut ApiStatus)>(f: F) {
+        f(&mut STATUS.lock().unwrap_or_else(|error| error.into_inner()));
+    }
+
+    pub fn set_last_score(score: &Score) {
+        let last_score = json!({
+            "id": score.id,
+            "artist": score.beatmapset.artist,
+            "title": score.beatmapset.title,
+            "difficulty": score.beatmap.version,
+            "passed": score.passed,
+            "ended_at": score.ended_at,
+        });
+
+        Self::update_status(|status| status.last_score = Some(last_score));
+    }
+
+    pub fn set_last_scrobble(backend: &'static str, artist: &str, title: &str, error: Option<String>) {
+        let error = error.map(|error| strip_ansi_codes(&error).to_string());
+        let last_scrobble = json!({ "artist": artist, "title": title, "error": error, "at": Utc::now().to_rfc3339() });
+        Self::update_status(|status| _ = status.last_scrobbles.insert(backend, last_scrobble));
+    }
+
+    pub fn set_last_error(tag: &str, error: String) {
+        let last_error = json!({ "tag": tag, "message": strip_ansi_codes(&error), "at": Utc::now().to_rfc3339() });
+        Self::update_status(|status| status.last_error = Some(last_error));
+    }
+
+    fn get_status_json() -> String {
+        let status = STATUS.lock().unwrap_or_else(|error| error.into_inner());
+        let mut value = json!(*status);
+        value["version"] = env!("CARGO_PKG_VERSION").into();
+        value.to_string()
+    }
+
+    fn get_action(method: &Method, url: &str) -> Option<ScrobblerAction> {
+        if *method != Method::Post {
+            return None;
+        }
+
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+        match path {
+            "/pause" => {
+                let duration_secs = query.split('&').find_map(|param| param.strip_prefix("duration_secs=")?.parse::<u64>().ok());
+                Some(ScrobblerAction::Pause(duration_secs.map(Duration::from_secs)))
+            },
+            "/resume" => Some(ScrobblerAction::Resume),
+            "/poll" => Some(ScrobblerAction::Poll),
+            "/reload" => Some(ScrobblerAction::Reload),
+            _ => None,
+        }
+    }
+
+    pub fn serve(config: &ApiConfig, sender: Sender<ScrobblerAction>) -> Result<()> {
+        let server = Server::http(&config.address).map_err(|error| anyhow!("Could not start API server: {error}"))?;
+
+        Logger::success("API", format!("Listening on {}.", format!("http://{}", config.address).bright_blue()), false);
+
+        let token = config.token.as_ref().map(|token| token.to_string());
+
+        spawn(move || {
+            for request in server.incoming_requests() {
+                let url = request.url().to_string();
+                let action_name = url.split('?').next().unwrap_or_default().trim_start_matches('/').to_string();
+                let get_header = |name: &'static str| {
+                    request.headers().iter().find(|header| header.field.equiv(name)).map(|header| header.value.as_str())
+                };
+
+                // Browsers attach an origin to cross-site requests, which are rejected so that web pages can't control the scrobbler
+                let (status_code, body) = if get_header("origin").is_some() {
+                    (403, json!({ "error": "Cross-origin requests are not allowed." }).to_string())
+                } else if token.as_ref().is_some_and(|token| get_header("authorization") != Some(&format!("Bearer {token}"))) {
+                    (401, json!({ "error": "Invalid or missing token." }).to_string())
+                } else if *request.method() == Method::Get && url == "/status" {
+                    (200, Self::get_status_json())
+                } else if let Some(action) = Self::get_action(request.method(), &url) {
+                    // Plain HTML forms can't set custom headers
+                    if get_header(ACTION_HEADER) != Some("1") {
+                        (403, json!({ "error": format!("Actions require the {ACTION_HEADER}: 1 header.") }).to_string())
+                    } else {
+                        Logger::success("API", format!("Received {} action.", action_name.bright_blue()), false);
+
+                        match sender.send(action) {
+                            Ok(_) => (202, json!({ "queued": action_name }).to_string()),
+                            Err(_) => (503, json!({ "error": "Scrobbler is not running." }).to_string()),
+                        }
+                    }
+                } else {
+                    (404, json!({ "error": "Not found." }).to_string())
+                };
+
+                let content_type = Header::from_bytes("content-type", "application/json").unwrap();
+                _ = request.respond(Response::from_string(body).with_status_code(status_code).with_header(content_type));
+            }
+        });
+
+        Ok(())
+    }
+}
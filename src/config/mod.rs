@@ -0,0 +1,1037 @@
+mod validation;
+
+use crate::{logger::Logger, presets::RedirectPreset, scores::Score};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use keyring::Entry;
+use md5::{Digest, compute};
+use regex::{Regex, RegexBuilder};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, MapAccess, SeqAccess, Visitor, value::MapAccessDeserializer},
+};
+use serde_json::{Value, to_string};
+use serde_regex::Serde as SerdeRegex;
+use std::{
+    collections::BTreeMap,
+    env::var,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    fs::{canonicalize, read_to_string},
+    marker::PhantomData,
+    path::PathBuf,
+};
+use toml::{Deserializer as TomlDeserializer, Value as TomlValue};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Config {
+    pub scrobbler: ScrobblerConfig,
+
+    #[serde(default)]
+    pub logger: LoggerConfig,
+
+    pub metrics: Option<MetricsConfig>,
+    pub api: Option<ApiConfig>,
+
+    pub last_fm: Option<LastfmConfig>,
+    pub listenbrainz: Option<ListenBrainzConfig>,
+}
+
+impl Config {
+    fn get_path() -> Result<PathBuf> {
+        let env_config_path = var("OSU_SCROBBLER_CONFIG_PATH");
+        let config_path = env_config_path.as_deref().unwrap_or("config.toml");
+        canonicalize(config_path).context("Could not resolve the path to config file.")
+    }
+
+    pub fn get_data_path(file_name: &str) -> Result<PathBuf> {
+        let config_path = Self::get_path()?;
+        let config_dir = config_path.parent().context("Could not resolve the directory of config file.")?;
+        Ok(config_dir.join(file_name))
+    }
+
+    fn parse(config_string: &str) -> Result<(Self, Vec<String>)> {
+        let mut unknown_keys = vec![];
+        let deserializer = TomlDeserializer::parse(config_string).context("An error occurred while parsing config file.")?;
+        let result = serde_ignored::deserialize::<_, _, Self>(deserializer, |path| {
+            let mut segments = vec![];
+            validation::get_path_segments(&path, &mut segments);
+            unknown_keys.push(segments);
+        });
+        let get_unknown_key_warnings = |unknown_keys: &[Vec<String>]| {
+            unknown_keys
+                .iter()
+                .filter(|segments| !validation::is_removed_key(segments))
+                .map(|segments| validation::get_unknown_key_warning(config_string, segments))
+                .collect::<Vec<String>>()
+        };
+
+        let config = match result {
+            Ok(config) => config,
+            Err(error) => {
+                let (section_errors, unknown_keys) = validation::get_errors(config_string);
+                let mut errors = validation::get_removed_key_errors(config_string, &unknown_keys);
+                errors.extend(section_errors);
+
+                if errors.is_empty() {
+                    return Err(error).context("An error occurred while parsing config file.");
+                }
+
+                let error_count = errors.len();
+                errors.extend(get_unknown_key_warnings(&unknown_keys));
+
+                return Err(anyhow!(errors.join("\n\n")))
+                    .context(format!("Found {} error(s) while parsing config file.", error_count.to_string().bright_blue()));
+            },
+        };
+
+        let errors = validation::get_removed_key_errors(config_string, &unknown_keys);
+
+        if !errors.is_empty() {
+            return Err(anyhow!(errors.join("\n\n"))).context("An error occurred while parsing config file.");
+        }
+
+        if config.last_fm.is_none() && config.listenbrainz.is_none() {
+            bail!("Please provide configuration for at least one scrobbler.");
+        }
+
+        let mut warnings = get_unknown_key_warnings(&unknown_keys);
+        warnings.extend(validation::get_rule_warnings(&config));
+
+        Ok((config, warnings))
+    }
+
+    pub fn init() -> Result<(Self, PathBuf, Digest)> {
+        let config_path = Self::get_path()?;
+        let config_string = read_to_string(&config_path).context("An error occurred while trying to read config file.")?;
+        let (config, warnings) = Config::parse(&config_string)?;
+
+        Logger::configure(&config.logger)?;
+
+        for warning in warnings {
+            Logger::warn("Config", warning, false);
+        }
+
+        Ok((config, config_path, compute(config_string)))
+    }
+
+    // Saving without changes or touching the file does not count as a change since the content hash stays the same
+    // The new hash is returned rather than stored, so the caller only commits it once the new config has been applied
+    pub fn read_if_changed(path: &PathBuf, config_hash: &Digest) -> Result<Option<(Self, Digest)>> {
+        let config_string = read_to_string(path).context("An error occurred while trying to read config file.")?;
+        let new_config_hash = compute(&config_string);
+
+        if new_config_hash == *config_hash {
+            return Ok(None);
+        }
+
+        let (config, warnings) = Config::parse(&config_string)?;
+
+        for warning in warnings {
+            Logger::warn("Config", warning, false);
+        }
+
+        Ok(Some((config, new_config_hash)))
+    }
+
+    pub fn run_check_command() -> Result<()> {
+        let config_path = Self::get_path()?;
+        let config_string = read_to_string(&config_path).context("An error occurred while trying to read config file.")?;
+        let (config, warnings) = Config::parse(&config_string)?;
+
+        for warning in &warnings {
+            Logger::warn("Config", warning, false);
+        }
+
+        Score::get_user_recent(&config.scrobbler).context("Could not verify user ID.")?;
+
+        Logger::success(
+            "Config",
+            format!(
+                "{} is valid with {} warning(s).",
+                config_path.to_string_lossy().bright_blue(),
+                warnings.len().to_string().bright_blue(),
+            ),
+            false,
+        );
+
+        Ok(())
+    }
+
+    // Lists the keys of [scrobbler] and the other top-level sections whose values differ between both configs
+    pub fn get_changed_keys(config_value: &Value, new_config_value: &Value) -> Result<Vec<String>> {
+        let mut changed_keys = vec![];
+
+        for (section, value) in config_value.as_object().context("Could not get config value as an object.")? {
+            if section != "scrobbler" {
+                if to_string(&value)? != to_string(&new_config_value[section])? {
+                    changed_keys.push(section.clone());
+                }
+
+                continue;
+            }
+
+            for (key, value) in value.as_object().context("Could not get scrobbler config value as an object.")? {
+                if to_string(&value)? != to_string(&new_config_value[section][key])? {
+                    changed_keys.push(key.clone());
+                }
+            }
+        }
+
+        Ok(changed_keys)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MetricsConfig {
+    #[serde(default = "MetricsConfig::address_default")]
+    pub address: String,
+}
+
+impl MetricsConfig {
+    fn address_default() -> String {
+        "127.0.0.1:9898".into()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiConfig {
+    #[serde(default = "ApiConfig::address_default")]
+    pub address: String,
+
+    pub token: Option<SensitiveString>,
+}
+
+impl ApiConfig {
+    fn address_default() -> String {
+        "127.0.0.1:9899".into()
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug)]
+pub struct LoggerConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+
+    #[serde(default)]
+    pub level: LogLevel,
+
+    #[serde(deserialize_with = "deserialize_case_insensitive_map", default)]
+    pub tags: BTreeMap<String, LogLevel>,
+
+    #[serde(default)]
+    pub file: Option<LoggerFileConfig>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct LoggerFileConfig {
+    pub path: String,
+
+    #[serde(default = "LoggerFileConfig::max_size_bytes_default")]
+    pub max_size_bytes: u64,
+
+    #[serde(default = "LoggerFileConfig::max_files_default")]
+    pub max_files: u32,
+}
+
+impl LoggerFileConfig {
+    fn max_size_bytes_default() -> u64 {
+        10 * 1024 * 1024
+    }
+
+    fn max_files_default() -> u32 {
+        5
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+
+    #[default]
+    Info,
+
+    Warn,
+    Error,
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            Self::Trace => "trace",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+
+    Json,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ScrobblerConfig {
+    pub user_id: u64,
+
+    #[serde(default)]
+    pub mode: Mode,
+
+    #[serde(default = "ScrobblerConfig::use_original_metadata_default")]
+    pub use_original_metadata: bool,
+
+    #[serde(default)]
+    pub artist_metadata: Option<MetadataPreference>,
+
+    #[serde(default)]
+    pub title_metadata: Option<MetadataPreference>,
+
+    #[serde(default = "ScrobblerConfig::original_scripts_default")]
+    pub original_scripts: Vec<Script>,
+
+    #[serde(default)]
+    pub fetch_album_names: bool,
+
+    #[serde(default)]
+    pub musicbrainz: ScrobblerMusicBrainzConfig,
+
+    #[serde(default)]
+    pub album: ScrobblerAlbumConfig,
+
+    #[serde(default = "ScrobblerConfig::min_beatmap_length_secs_default")]
+    pub min_beatmap_length_secs: u32,
+
+    #[serde(default)]
+    pub scrobble_fails: bool,
+
+    #[serde(default)]
+    pub paused: bool,
+
+    #[serde(default, deserialize_with = "deserialize_datetime")]
+    pub paused_until: Option<DateTime<FixedOffset>>,
+
+    #[serde(default = "ScrobblerConfig::pause_file_default")]
+    pub pause_file: String,
+
+    #[serde(default)]
+    pub log_scrobbles: bool,
+
+    #[serde(default)]
+    pub scrobble_log: ScrobblerScrobbleLogConfig,
+
+    #[serde(default)]
+    pub redirects: ScrobblerRedirectsConfig,
+
+    #[serde(default)]
+    pub featured_artists: FeaturedArtistPolicy,
+
+    #[serde(default)]
+    pub split_artist_separators: bool,
+
+    #[serde(default)]
+    pub blacklist: ScrobblerBlacklistConfig,
+}
+
+impl ScrobblerConfig {
+    pub fn get_artist_metadata(&self) -> MetadataPreference {
+        self.artist_metadata.unwrap_or(self.get_default_metadata())
+    }
+
+    pub fn get_title_metadata(&self) -> MetadataPreference {
+        self.title_metadata.unwrap_or(self.get_default_metadata())
+    }
+
+    fn get_default_metadata(&self) -> MetadataPreference {
+        if self.use_original_metadata { MetadataPreference::Original } else { MetadataPreference::Romanized }
+    }
+
+    fn use_original_metadata_default() -> bool {
+        true
+    }
+
+    fn original_scripts_default() -> Vec<Script> {
+        vec![Script::Latin, Script::Hiragana, Script::Katakana, Script::Han]
+    }
+
+    fn min_beatmap_length_secs_default() -> u32 {
+        60
+    }
+
+    fn pause_file_default() -> String {
+        "pause".into()
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Default,
+
+    Osu,
+    Taiko,
+    Fruits,
+    Mania,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataPreference {
+    Romanized,
+    Original,
+    Auto,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Hebrew,
+    Arabic,
+    Thai,
+    Hiragana,
+    Katakana,
+    Han,
+    Hangul,
+}
+
+impl Script {
+    pub fn contains(&self, char: char) -> bool {
+        let ranges: &[(u32, u32)] = match self {
+            Self::Latin => &[(0x41, 0x5A), (0x61, 0x7A), (0xC0, 0x24F), (0x1E00, 0x1EFF), (0xFF21, 0xFF3A), (0xFF41, 0xFF5A)],
+            Self::Greek => &[(0x370, 0x3FF), (0x1F00, 0x1FFF)],
+            Self::Cyrillic => &[(0x400, 0x52F)],
+            Self::Hebrew => &[(0x590, 0x5FF)],
+            Self::Arabic => &[(0x600, 0x6FF), (0x750, 0x77F)],
+            Self::Thai => &[(0xE00, 0xE7F)],
+            Self::Hiragana => &[(0x3040, 0x309F)],
+            Self::Katakana => &[(0x30A0, 0x30FF), (0x31F0, 0x31FF), (0xFF66, 0xFF9F)],
+            Self::Han => &[(0x3005, 0x3007), (0x3400, 0x4DBF), (0x4E00, 0x9FFF), (0xF900, 0xFAFF), (0x20000, 0x2FA1F)],
+            Self::Hangul => &[(0x1100, 0x11FF), (0x3130, 0x318F), (0xAC00, 0xD7AF)],
+        };
+
+        ranges.iter().any(|(start, end)| (*start..=*end).contains(&(char as u32)))
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FeaturedArtistPolicy {
+    #[default]
+    Keep,
+
+    MoveToTitle,
+    Split,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct ScrobblerScrobbleLogConfig {
+    #[serde(default = "ScrobblerScrobbleLogConfig::path_default")]
+    pub path: String,
+
+    #[serde(default)]
+    pub format: ScrobbleLogFormat,
+
+    #[serde(default)]
+    pub max_size_bytes: u64,
+
+    #[serde(default = "ScrobblerScrobbleLogConfig::max_files_default")]
+    pub max_files: u32,
+}
+
+impl ScrobblerScrobbleLogConfig {
+    fn path_default() -> String {
+        "scrobble.log".into()
+    }
+
+    fn max_files_default() -> u32 {
+        5
+    }
+}
+
+impl Default for ScrobblerScrobbleLogConfig {
+    fn default() -> Self {
+        Self { path: Self::path_default(), format: ScrobbleLogFormat::default(), max_size_bytes: 0, max_files: Self::max_files_default() }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrobbleLogFormat {
+    #[default]
+    Pipe,
+
+    Csv,
+    Jsonl,
+    Rockbox,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ScrobblerMusicBrainzConfig {
+    #[serde(default = "ScrobblerMusicBrainzConfig::server_url_default")]
+    pub server_url: String,
+
+    #[serde(default = "ScrobblerMusicBrainzConfig::contact_default")]
+    pub contact: String,
+
+    #[serde(default = "ScrobblerMusicBrainzConfig::min_confidence_default")]
+    pub min_confidence: f64,
+
+    #[serde(default = "ScrobblerMusicBrainzConfig::cache_default")]
+    pub cache: bool,
+
+    #[serde(default = "ScrobblerMusicBrainzConfig::cache_ttl_secs_default")]
+    pub cache_ttl_secs: u64,
+
+    #[serde(default = "ScrobblerMusicBrainzConfig::cache_negative_ttl_secs_default")]
+    pub cache_negative_ttl_secs: u64,
+}
+
+impl ScrobblerMusicBrainzConfig {
+    fn server_url_default() -> String {
+        "https://musicbrainz.org".into()
+    }
+
+    fn contact_default() -> String {
+        "https://github.com/flazepe/osu-scrobbler".into()
+    }
+
+    fn min_confidence_default() -> f64 {
+        0.6
+    }
+
+    fn cache_default() -> bool {
+        true
+    }
+
+    fn cache_ttl_secs_default() -> u64 {
+        60 * 60 * 24 * 30
+    }
+
+    fn cache_negative_ttl_secs_default() -> u64 {
+        60 * 60 * 24
+    }
+}
+
+impl Default for ScrobblerMusicBrainzConfig {
+    fn default() -> Self {
+        Self {
+            server_url: Self::server_url_default(),
+            contact: Self::contact_default(),
+            min_confidence: Self::min_confidence_default(),
+            cache: Self::cache_default(),
+            cache_ttl_secs: Self::cache_ttl_secs_default(),
+            cache_negative_ttl_secs: Self::cache_negative_ttl_secs_default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ScrobblerAlbumConfig {
+    #[serde(default = "ScrobblerAlbumConfig::strategies_default")]
+    pub strategies: Vec<AlbumStrategy>,
+
+    #[serde(default = "ScrobblerAlbumConfig::source_template_default")]
+    pub source_template: String,
+
+    #[serde(default)]
+    pub fixed: Option<String>,
+
+    #[serde(default)]
+    pub beatmapsets: BTreeMap<String, String>,
+}
+
+impl ScrobblerAlbumConfig {
+    fn strategies_default() -> Vec<AlbumStrategy> {
+        vec![AlbumStrategy::MusicBrainz]
+    }
+
+    fn source_template_default() -> String {
+        "{source}".into()
+    }
+}
+
+impl Default for ScrobblerAlbumConfig {
+    fn default() -> Self {
+        Self {
+            strategies: Self::strategies_default(),
+            source_template: Self::source_template_default(),
+            fixed: None,
+            beatmapsets: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AlbumStrategy {
+    MusicBrainz,
+    Source,
+    Fixed,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerRedirectsConfig {
+    #[serde(default)]
+    pub presets: Vec<RedirectPreset>,
+
+    #[serde(default)]
+    pub artists: ScrobblerRedirectsTypeConfig,
+
+    #[serde(default)]
+    pub titles: ScrobblerRedirectsTypeConfig,
+
+    #[serde(default)]
+    pub albums: ScrobblerRedirectsAlbumsConfig,
+
+    #[serde(default)]
+    pub combined: ScrobblerRedirectsCombinedConfig,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerRedirectsTypeConfig {
+    #[serde(deserialize_with = "deserialize_case_insensitive_redirects_vec", default)]
+    pub equal_matches: Vec<Redirect<String>>,
+
+    #[serde(deserialize_with = "deserialize_regex_redirects_vec", default)]
+    pub regex_matches: Vec<Redirect<Regex>>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerRedirectsAlbumsConfig {
+    #[serde(deserialize_with = "deserialize_case_insensitive_redirects_vec", default)]
+    pub equal_matches: Vec<Redirect<String>>,
+
+    #[serde(deserialize_with = "deserialize_regex_redirects_vec", default)]
+    pub regex_matches: Vec<Redirect<Regex>>,
+
+    #[serde(deserialize_with = "deserialize_track_redirects_vec", default)]
+    pub track_matches: Vec<(String, String, String)>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerRedirectsCombinedConfig {
+    #[serde(default)]
+    pub regex_matches: Vec<CombinedRedirect>,
+}
+
+#[derive(Debug)]
+pub struct CombinedRedirect {
+    pub regex: Regex,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for CombinedRedirect {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CombinedRedirectVisitor;
+
+        impl<'de> Visitor<'de> for CombinedRedirectVisitor {
+            type Value = (SerdeRegex<Regex>, Option<String>, Option<String>);
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter.write_str(
+                    "a tuple containing a regex pattern, artist replacer string and title replacer string, or a table containing match, artist and title",
+                )
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let regex = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let artist = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let title = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                Ok((regex, Some(artist), Some(title)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let table = CombinedRedirectTable::deserialize(MapAccessDeserializer::new(map))?;
+                Ok((table.regex, table.artist, table.title))
+            }
+        }
+
+        let (SerdeRegex(regex), artist, title) = deserializer.deserialize_any(CombinedRedirectVisitor)?;
+        let regex = RegexBuilder::new(regex.as_str()).case_insensitive(true).build().map_err(de::Error::custom)?;
+
+        // Fall back to the named capture groups when no replacer is given
+        let has_group = |name| regex.capture_names().flatten().any(|group| group == name);
+        let artist = artist.or_else(|| has_group("artist").then(|| "${artist}".into()));
+        let title = title.or_else(|| has_group("title").then(|| "${title}".into()));
+
+        Ok(Self { regex, artist, title })
+    }
+}
+
+impl Serialize for CombinedRedirect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.regex.as_str(), &self.artist, &self.title).serialize(serializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CombinedRedirectTable {
+    #[serde(rename = "match")]
+    regex: SerdeRegex<Regex>,
+
+    artist: Option<String>,
+    title: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Redirect<T> {
+    pub old: T,
+    pub new: String,
+    pub conditions: RedirectConditions,
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Redirect<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RedirectVisitor<T>(PhantomData<T>);
+
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for RedirectVisitor<T> {
+            type Value = Redirect<T>;
+
+            fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+                formatter
+                    .write_str("a tuple containing a match and replacer string, or a table containing match, replace and when_* conditions")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let old = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let new = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                Ok(Redirect { old, new, conditions: RedirectConditions::default() })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let table = RedirectTable::<T>::deserialize(MapAccessDeserializer::new(map))?;
+
+                Ok(Redirect {
+                    old: table.old,
+                    new: table.replace,
+                    conditions: RedirectConditions {
+                        when_artist: table.when_artist,
+                        when_title: table.when_title,
+                        when_beatmapset_id: table.when_beatmapset_id,
+                        when_creator: table.when_creator.map(|creator| creator.to_lowercase()),
+                        when_mode: table.when_mode,
+                    },
+                })
+            }
+        }
+
+        deserializer.deserialize_any(RedirectVisitor(PhantomData))
+    }
+}
+
+impl<T: Display> Serialize for Redirect<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.old.to_string(), &self.new, &self.conditions).serialize(serializer)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RedirectTable<T> {
+    #[serde(rename = "match")]
+    old: T,
+
+    replace: String,
+
+    #[serde(deserialize_with = "deserialize_optional_regex", default)]
+    when_artist: Option<Regex>,
+
+    #[serde(deserialize_with = "deserialize_optional_regex", default)]
+    when_title: Option<Regex>,
+
+    when_beatmapset_id: Option<u32>,
+    when_creator: Option<String>,
+    when_mode: Option<Mode>,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct RedirectConditions {
+    #[serde(serialize_with = "serde_regex::serialize")]
+    pub when_artist: Option<Regex>,
+
+    #[serde(serialize_with = "serde_regex::serialize")]
+    pub when_title: Option<Regex>,
+
+    pub when_beatmapset_id: Option<u32>,
+    pub when_creator: Option<String>,
+    pub when_mode: Option<Mode>,
+}
+
+impl RedirectConditions {
+    pub fn is_met(&self, score: &Score) -> bool {
+        let beatmapset = &score.beatmapset;
+
+        self.when_artist.as_ref().is_none_or(|regex| regex.is_match(&beatmapset.artist) || regex.is_match(&beatmapset.artist_unicode))
+            && self.when_title.as_ref().is_none_or(|regex| regex.is_match(&beatmapset.title) || regex.is_match(&beatmapset.title_unicode))
+            && self.when_beatmapset_id.is_none_or(|beatmapset_id| beatmapset_id == beatmapset.id)
+            && self.when_creator.as_ref().is_none_or(|creator| *creator == beatmapset.creator.to_lowercase())
+            && self.when_mode.as_ref().is_none_or(|mode| *mode == Mode::Default || *mode == score.get_mode())
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerBlacklistConfig {
+    pub artists: ScrobblerBlacklistTypeConfig,
+    pub titles: ScrobblerBlacklistTypeConfig,
+    pub difficulties: ScrobblerBlacklistTypeConfig,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug)]
+pub struct ScrobblerBlacklistTypeConfig {
+    #[serde(deserialize_with = "deserialize_case_insensitive_vec", default)]
+    pub equal_matches: Vec<String>,
+
+    #[serde(deserialize_with = "deserialize_regex_vec", serialize_with = "serde_regex::serialize", default)]
+    pub regex_matches: Vec<Regex>,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LastfmConfig {
+    pub username: String,
+    pub session_key: Option<SensitiveString>,
+    pub api_key: SensitiveString,
+    pub api_secret: SensitiveString,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct ListenBrainzConfig {
+    pub user_token: SensitiveString,
+}
+
+#[derive(PartialEq)]
+pub struct SensitiveString(String);
+
+impl SensitiveString {
+    const KEYRING_SERVICE: &str = "osu-scrobbler";
+
+    // Secrets can be written as is, or referenced as env:VAR, file:/path or keyring:name to keep them out of the config file
+    fn resolve(value: &str) -> Result<String> {
+        if let Some(name) = value.strip_prefix("env:") {
+            return var(name).map_err(|_| anyhow!("Environment variable {name} is not set."));
+        }
+
+        if let Some(path) = value.strip_prefix("file:") {
+            let path = Config::get_data_path(path)?;
+            let secret =
+                read_to_string(&path).map_err(|error| anyhow!("Could not read secret file {}: {error}.", path.to_string_lossy()))?;
+            return Ok(secret.trim_end().into());
+        }
+
+        if let Some(name) = value.strip_prefix("keyring:") {
+            return Entry::new(Self::KEYRING_SERVICE, name)
+                .and_then(|entry| entry.get_password())
+                .map_err(|error| anyhow!("Could not read keyring entry {name}: {error}."));
+        }
+
+        Ok(value.into())
+    }
+}
+
+impl<'de> Deserialize<'de> for SensitiveString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::resolve(&value).map(Self).map_err(de::Error::custom)
+    }
+}
+
+impl Display for SensitiveString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Debug for SensitiveString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "<redacted>")
+    }
+}
+
+impl Serialize for SensitiveString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str("<redacted>")
+    }
+}
+
+// Accepts both native TOML datetimes and quoted RFC 3339 strings, as long as they include an offset
+fn deserialize_datetime<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+    let datetime = match TomlValue::deserialize(deserializer)? {
+        TomlValue::Datetime(datetime) => datetime.to_string(),
+        TomlValue::String(string) => string,
+        _ => return Err(de::Error::custom("expected a datetime")),
+    };
+
+    DateTime::parse_from_rfc3339(&datetime)
+        .map(Some)
+        .map_err(|error| de::Error::custom(format!("invalid datetime {datetime:?}, expected one with an offset such as Z: {error}")))
+}
+
+fn deserialize_case_insensitive_redirects_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Redirect<String>>, D::Error> {
+    struct RegexCaseInsensitiveRedirectsVecVisitor;
+
+    impl<'de> Visitor<'de> for RegexCaseInsensitiveRedirectsVecVisitor {
+        type Value = Vec<Redirect<String>>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+            formatter.write_str("an array of tuples containing a string and replacer string")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(mut redirect) = seq.next_element::<Redirect<String>>()? {
+                redirect.old = redirect.old.to_lowercase();
+                vec.push(redirect);
+            }
+
+            Ok(vec)
+        }
+    }
+
+    deserializer.deserialize_seq(RegexCaseInsensitiveRedirectsVecVisitor)
+}
+
+fn deserialize_case_insensitive_map<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, T>, D::Error> {
+    let map = BTreeMap::<String, T>::deserialize(deserializer)?;
+    Ok(map.into_iter().map(|(key, value)| (key.to_lowercase(), value)).collect())
+}
+
+fn deserialize_track_redirects_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, String, String)>, D::Error> {
+    struct TrackRedirectsVecVisitor;
+
+    impl<'de> Visitor<'de> for TrackRedirectsVecVisitor {
+        type Value = Vec<(String, String, String)>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+            formatter.write_str("an array of tuples containing an artist string, title string and replacer string")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some((artist, title, new)) = seq.next_element::<(String, String, String)>()? {
+                vec.push((artist.to_lowercase(), title.to_lowercase(), new));
+            }
+
+            Ok(vec)
+        }
+    }
+
+    deserializer.deserialize_seq(TrackRedirectsVecVisitor)
+}
+
+fn deserialize_case_insensitive_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    struct CaseInsensitiveVecVisitor;
+
+    impl<'de> Visitor<'de> for CaseInsensitiveVecVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+            formatter.write_str("an array of strings")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(element) = seq.next_element::<String>()? {
+                vec.push(element.to_lowercase());
+            }
+
+            Ok(vec)
+        }
+    }
+
+    deserializer.deserialize_seq(CaseInsensitiveVecVisitor)
+}
+
+fn deserialize_regex_redirects_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Redirect<Regex>>, D::Error> {
+    struct RegexRedirectsVecVisitor;
+
+    impl<'de> Visitor<'de> for RegexRedirectsVecVisitor {
+        type Value = Vec<Redirect<Regex>>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+            formatter.write_str("an array of tuples containing a regex pattern and replacer string")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(redirect) = seq.next_element::<Redirect<SerdeRegex<Regex>>>()? {
+                let case_insensitive_regex =
+                    RegexBuilder::new(redirect.old.0.as_str()).case_insensitive(true).build().map_err(de::Error::custom)?;
+                vec.push(Redirect { old: case_insensitive_regex, new: redirect.new, conditions: redirect.conditions });
+            }
+
+            Ok(vec)
+        }
+    }
+
+    deserializer.deserialize_seq(RegexRedirectsVecVisitor)
+}
+
+fn deserialize_optional_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+    let Some(SerdeRegex(regex)) = Option::<SerdeRegex<Regex>>::deserialize(deserializer)? else { return Ok(None) };
+    RegexBuilder::new(regex.as_str()).case_insensitive(true).build().map(Some).map_err(de::Error::custom)
+}
+
+fn deserialize_regex_vec<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Regex>, D::Error> {
+    struct RegexVecVisitor;
+
+    impl<'de> Visitor<'de> for RegexVecVisitor {
+        type Value = Vec<Regex>;
+
+        fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+            formatter.write_str("an array of regex pattern strings")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+            while let Some(SerdeRegex(regex)) = seq.next_element::<SerdeRegex<Regex>>()? {
+                let case_insensitive_regex = RegexBuilder::new(regex.as_str()).case_insensitive(true).build().map_err(de::Error::custom)?;
+                vec.push(case_insensitive_regex);
+            }
+
+            Ok(vec)
+        }
+    }
+
+    deserializer.deserialize_seq(RegexVecVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScrobblerConfig;
+
+    fn get_paused_until(paused_until: &str) -> Result<String, String> {
+        toml::from_str::<ScrobblerConfig>(&format!("user_id = 1\npaused_until = {paused_until}"))
+            .map(|config| config.paused_until.map(|paused_until| paused_until.to_rfc3339()).unwrap_or_default())
+            .map_err(|error| error.message().to_string())
+    }
+
+    #[test]
+    fn paused_until() {
+        assert_eq!(get_paused_until("2026-10-20T00:00:00Z").as_deref(), Ok("2026-10-20T00:00:00+00:00"));
+        assert_eq!(get_paused_until("2026-10-20T09:00:00+09:00").as_deref(), Ok("2026-10-20T09:00:00+09:00"));
+        assert_eq!(get_paused_until("\"2026-10-20T00:00:00Z\"").as_deref(), Ok("2026-10-20T00:00:00+00:00"));
+        assert!(get_paused_until("2026-10-20T00:00:00").is_err());
+        assert!(get_paused_until("\"tomorrow\"").is_err());
+    }
+}
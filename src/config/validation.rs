@@ -0,0 +1,481 @@
+use crate::config::{
+    ApiConfig, Config, LastfmConfig, ListenBrainzConfig, LoggerConfig, MetricsConfig, Redirect, RedirectConditions, ScrobblerAlbumConfig,
+    ScrobblerBlacklistConfig, ScrobblerBlacklistTypeConfig, ScrobblerConfig, ScrobblerMusicBrainzConfig, ScrobblerRedirectsAlbumsConfig,
+    ScrobblerRedirectsCombinedConfig, ScrobblerRedirectsConfig, ScrobblerRedirectsTypeConfig, ScrobblerScrobbleLogConfig,
+};
+use crate::presets::RedirectPreset;
+use colored::Colorize;
+use regex::Regex;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde_ignored::Path;
+use serde_json::{Map, Value, to_value};
+use toml::{
+    Spanned,
+    de::{DeTable, DeValue},
+};
+
+type SectionCheck = fn(&str, &[&str], Spanned<DeValue>, &mut SectionDiagnostics);
+
+pub fn get_path_segments(path: &Path, segments: &mut Vec<String>) {
+    match path {
+        Path::Root => {},
+        Path::Seq { parent, index } => {
+            get_path_segments(parent, segments);
+            segments.push(index.to_string());
+        },
+        Path::Map { parent, key } => {
+            get_path_segments(parent, segments);
+            segments.push(key.clone());
+        },
+        Path::Some { parent } | Path::NewtypeStruct { parent } | Path::NewtypeVariant { parent } => get_path_segments(parent, segments),
+    }
+}
+
+fn get_location(config_string: &str, segments: &[String]) -> Option<(usize, usize)> {
+    let root = DeTable::parse(config_string).ok()?;
+    let mut value = None::<&DeValue>;
+    let mut offset = 0;
+
+    for segment in segments {
+        let (span, element) = match value {
+            None => root.get_ref().iter().find(|(key, _)| key.get_ref() == segment).map(|(key, element)| (key.span(), element))?,
+            Some(DeValue::Table(table)) => {
+                table.iter().find(|(key, _)| key.get_ref() == segment).map(|(key, element)| (key.span(), element))?
+            },
+            Some(DeValue::Array(array)) => array.get(segment.parse::<usize>().ok()?).map(|element| (element.span(), element))?,
+            Some(_) => return None,
+        };
+
+        offset = span.start;
+        value = Some(element.get_ref());
+    }
+
+    let before = &config_string[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+
+    Some((line, column))
+}
+
+pub fn get_unknown_key_warning(config_string: &str, segments: &[String]) -> String {
+    let location = get_location(config_string, segments)
+        .map(|(line, column)| format!(" at line {}, column {}", line.to_string().bright_blue(), column.to_string().bright_blue()))
+        .unwrap_or_default();
+
+    format!("Unknown key {}{location} will be ignored.", segments.join(".").bright_blue())
+}
+
+// Keys that were supported before and now need action from the user rather than being silently ignored
+const REMOVED_KEYS: &[(&[&str], &str)] = &[(
+    &["last_fm", "password"],
+    "Last.fm passwords are no longer supported. Remove it and run `osu-scrobbler auth lastfm` to authorize with a session key instead.",
+)];
+
+pub fn is_removed_key(segments: &[String]) -> bool {
+    REMOVED_KEYS.iter().any(|(path, _)| segments == *path)
+}
+
+pub fn get_removed_key_errors(config_string: &str, unknown_keys: &[Vec<String>]) -> Vec<String> {
+    REMOVED_KEYS
+        .iter()
+        .filter(|(path, _)| unknown_keys.iter().any(|segments| segments == path))
+        .map(|(path, message)| {
+            let location = get_location(config_string, &path.iter().map(|segment| segment.to_string()).collect::<Vec<String>>())
+                .map(|(line, column)| format!(" at line {}, column {}", line.to_string().bright_blue(), column.to_string().bright_blue()))
+                .unwrap_or_default();
+
+            format!("Found key {}{location}. {message}", path.join(".").bright_blue())
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct SectionDiagnostics {
+    errors: Vec<(usize, String)>,
+    unknown_keys: Vec<Vec<String>>,
+}
+
+// Deserializing stops at the first invalid value, so the key it belongs to is left out and the rest of the table is checked again
+fn check_section<T: DeserializeOwned>(config_string: &str, path: &[&str], value: Spanned<DeValue>, diagnostics: &mut SectionDiagnostics) {
+    let span = value.span();
+    let mut value = value.into_inner();
+    let mut removed_keys = vec![];
+
+    loop {
+        let mut unknown_keys = vec![];
+        let result = serde_ignored::deserialize::<_, _, T>(Spanned::new(span.clone(), value.clone()).into_deserializer(), |ignored_path| {
+            let mut segments = path.iter().map(|segment| segment.to_string()).collect();
+            get_path_segments(&ignored_path, &mut segments);
+            unknown_keys.push(segments);
+        });
+
+        for segments in unknown_keys {
+            if !diagnostics.unknown_keys.contains(&segments) {
+                diagnostics.unknown_keys.push(segments);
+            }
+        }
+
+        let Err(mut error) = result else { break };
+
+        // A required key that was left out is already reported through its invalid value
+        if removed_keys.iter().any(|key| error.message() == format!("missing field `{key}`")) {
+            break;
+        }
+
+        let error_span = error.span().unwrap_or_default();
+        error.set_input(Some(config_string));
+        diagnostics.errors.push((error_span.start, error.to_string().trim_end().into()));
+
+        let DeValue::Table(table) = &mut value else { break };
+        let key = table
+            .iter()
+            .find(|(key, value)| key.span().start <= error_span.start && error_span.start < value.span().end)
+            .map(|(key, _)| key.clone());
+        let Some(key) = key else { break };
+
+        table.remove(&key);
+        removed_keys.push(key.into_inner().into_owned());
+    }
+}
+
+// Checks the given subsections on their own, then the rest of the table, which only works for subsections with defaults
+fn check_table<T: DeserializeOwned>(
+    config_string: &str,
+    path: &[&str],
+    value: Spanned<DeValue>,
+    subsections: &[(&str, SectionCheck)],
+    diagnostics: &mut SectionDiagnostics,
+) {
+    if !matches!(value.get_ref(), DeValue::Table(_)) {
+        return check_section::<T>(config_string, path, value, diagnostics);
+    }
+
+    let span = value.span();
+    let DeValue::Table(table) = value.into_inner() else { return };
+    let mut rest = DeTable::new();
+
+    for (key, value) in table {
+        match subsections.iter().find(|(subsection_key, _)| *subsection_key == key.get_ref().as_ref()) {
+            Some((subsection_key, check)) => check(config_string, &[path, &[subsection_key]].concat(), value, diagnostics),
+            None => _ = rest.insert(key, value),
+        }
+    }
+
+    check_section::<T>(config_string, path, Spanned::new(span, DeValue::Table(rest)), diagnostics);
+}
+
+fn check_scrobbler_section(config_string: &str, path: &[&str], value: Spanned<DeValue>, diagnostics: &mut SectionDiagnostics) {
+    check_table::<ScrobblerConfig>(
+        config_string,
+        path,
+        value,
+        &[
+            ("musicbrainz", check_section::<ScrobblerMusicBrainzConfig>),
+            ("album", check_section::<ScrobblerAlbumConfig>),
+            ("scrobble_log", check_section::<ScrobblerScrobbleLogConfig>),
+            ("redirects", check_redirects_section),
+            ("blacklist", check_section::<ScrobblerBlacklistConfig>),
+        ],
+        diagnostics,
+    );
+}
+
+fn check_redirects_section(config_string: &str, path: &[&str], value: Spanned<DeValue>, diagnostics: &mut SectionDiagnostics) {
+    check_table::<ScrobblerRedirectsConfig>(
+        config_string,
+        path,
+        value,
+        &[
+            ("presets", check_section::<Vec<RedirectPreset>>),
+            ("artists", check_section::<ScrobblerRedirectsTypeConfig>),
+            ("titles", check_section::<ScrobblerRedirectsTypeConfig>),
+            ("albums", check_section::<ScrobblerRedirectsAlbumsConfig>),
+            ("combined", check_section::<ScrobblerRedirectsCombinedConfig>),
+        ],
+        diagnostics,
+    );
+}
+
+// Deserializing stops at the first error, so every section is checked on its own to report all errors and unknown keys at once
+pub fn get_errors(config_string: &str) -> (Vec<String>, Vec<Vec<String>>) {
+    let Ok(root) = DeTable::parse(config_string) else { return (vec![], vec![]) };
+    let root = root.into_inner();
+    let mut diagnostics = SectionDiagnostics::default();
+
+    if !root.iter().any(|(key, _)| key.get_ref() == "scrobbler") {
+        diagnostics.errors.push((0, "Missing [scrobbler] section.".into()));
+    }
+
+    for (key, value) in root {
+        let check: SectionCheck = match key.get_ref().as_ref() {
+            "scrobbler" => check_scrobbler_section,
+            "logger" => check_section::<LoggerConfig>,
+            "metrics" => check_section::<MetricsConfig>,
+            "api" => check_section::<ApiConfig>,
+            "last_fm" => check_section::<LastfmConfig>,
+            "listenbrainz" => check_section::<ListenBrainzConfig>,
+            _ => {
+                diagnostics.unknown_keys.push(vec![key.get_ref().to_string()]);
+                continue;
+            },
+        };
+
+        check(config_string, &[key.get_ref()], value, &mut diagnostics);
+    }
+
+    diagnostics.errors.sort_by_key(|(offset, _)| *offset);
+
+    (diagnostics.errors.into_iter().map(|(_, error)| error).collect(), diagnostics.unknown_keys)
+}
+
+fn get_conditions(conditions: &RedirectConditions) -> Map<String, Value> {
+    match to_value(conditions) {
+        Ok(Value::Object(conditions)) => conditions.into_iter().filter(|(_, value)| !value.is_null()).collect(),
+        _ => Map::new(),
+    }
+}
+
+// Whether every score meeting the later conditions also meets the earlier ones
+fn covers(earlier: &RedirectConditions, later: &RedirectConditions) -> bool {
+    let later = get_conditions(later);
+    get_conditions(earlier).iter().all(|(key, value)| later.get(key) == Some(value))
+}
+
+// Equal redirects stop at the first match, so a later redirect for the same source never applies when an earlier one covers it
+fn check_equal_redirects(kind: &str, redirects: &[Redirect<String>], warnings: &mut Vec<String>) {
+    for (index, redirect) in redirects.iter().enumerate() {
+        let Some((previous_index, previous_redirect)) = redirects[..index].iter().enumerate().find(|(_, previous_redirect)| {
+            previous_redirect.old == redirect.old && covers(&previous_redirect.conditions, &redirect.conditions)
+        }) else {
+            continue;
+        };
+
+        if previous_redirect.new == redirect.new && get_conditions(&previous_redirect.conditions) == get_conditions(&redirect.conditions) {
+            warnings.push(format!("{kind} #{} duplicates #{} for source {}.", index + 1, previous_index + 1, redirect.old.bright_blue()));
+        } else {
+            warnings.push(format!(
+                "{kind} #{} for source {} is shadowed by #{}, which takes precedence for every score it applies to.",
+                index + 1,
+                redirect.old.bright_blue(),
+                previous_index + 1,
+            ));
+        }
+    }
+}
+
+// Regex redirects all apply in order, so only exact duplicates are reported
+fn check_regex_redirects(kind: &str, redirects: &[Redirect<Regex>], warnings: &mut Vec<String>) {
+    for (index, redirect) in redirects.iter().enumerate() {
+        let previous_index = redirects[..index].iter().position(|previous_redirect| {
+            previous_redirect.old.as_str() == redirect.old.as_str()
+                && previous_redirect.new == redirect.new
+                && get_conditions(&previous_redirect.conditions) == get_conditions(&redirect.conditions)
+        });
+
+        if let Some(previous_index) = previous_index {
+            warnings.push(format!(
+                "{kind} #{} duplicates #{} for pattern {}.",
+                index + 1,
+                previous_index + 1,
+                redirect.old.as_str().bright_blue(),
+            ));
+        }
+    }
+}
+
+// Regex redirects only run when no equal redirect matched, so they are skipped for sources an equal redirect with covering conditions handles
+fn check_regex_redirects_behind_equal(kind: &str, config: &ScrobblerRedirectsTypeConfig, warnings: &mut Vec<String>) {
+    for (index, redirect) in config.regex_matches.iter().enumerate() {
+        let equal_index = config.equal_matches.iter().position(|equal_redirect| {
+            covers(&equal_redirect.conditions, &redirect.conditions) && redirect.old.is_match(&equal_redirect.old)
+        });
+
+        if let Some(equal_index) = equal_index {
+            warnings.push(format!(
+                "{kind} regex redirect #{} never applies to source {} since equal redirect #{} takes precedence.",
+                index + 1,
+                config.equal_matches[equal_index].old.bright_blue(),
+                equal_index + 1,
+            ));
+        }
+    }
+}
+
+fn check_blacklisted_redirects(
+    kind: &str,
+    redirects: &[Redirect<String>],
+    blacklist: &ScrobblerBlacklistTypeConfig,
+    warnings: &mut Vec<String>,
+) {
+    for (index, redirect) in redirects.iter().enumerate() {
+        let is_blacklisted = blacklist.equal_matches.contains(&redirect.old)
+            || blacklist.regex_matches.iter().any(|regex: &Regex| regex.is_match(&redirect.old));
+
+        if is_blacklisted {
+            warnings.push(format!(
+                "{kind} #{} for source {} never applies since the source is blacklisted.",
+                index + 1,
+                redirect.old.bright_blue(),
+            ));
+        }
+    }
+}
+
+fn check_duplicate_entries(kind: &str, entries: &[String], warnings: &mut Vec<String>) {
+    for (index, entry) in entries.iter().enumerate() {
+        if entries[..index].contains(entry) {
+            warnings.push(format!("{kind} {} is listed more than once.", entry.bright_blue()));
+        }
+    }
+}
+
+pub fn get_rule_warnings(config: &Config) -> Vec<String> {
+    let redirects = &config.scrobbler.redirects;
+    let blacklist = &config.scrobbler.blacklist;
+    let mut warnings = vec![];
+
+    let presets = redirects.presets.iter().map(|preset| preset.to_string()).collect::<Vec<String>>();
+    check_duplicate_entries("Redirect preset", &presets, &mut warnings);
+
+    check_equal_redirects("Artist equal redirect", &redirects.artists.equal_matches, &mut warnings);
+    check_regex_redirects("Artist regex redirect", &redirects.artists.regex_matches, &mut warnings);
+    check_regex_redirects_behind_equal("Artist", &redirects.artists, &mut warnings);
+    check_equal_redirects("Title equal redirect", &redirects.titles.equal_matches, &mut warnings);
+    check_regex_redirects("Title regex redirect", &redirects.titles.regex_matches, &mut warnings);
+    check_regex_redirects_behind_equal("Title", &redirects.titles, &mut warnings);
+    check_equal_redirects("Album equal redirect", &redirects.albums.equal_matches, &mut warnings);
+    check_regex_redirects("Album regex redirect", &redirects.albums.regex_matches, &mut warnings);
+
+    for (index, (artist, title, _)) in redirects.albums.track_matches.iter().enumerate() {
+        let previous_index = redirects.albums.track_matches[..index]
+            .iter()
+            .position(|(other_artist, other_title, _)| other_artist == artist && other_title == title);
+
+        if let Some(previous_index) = previous_index {
+            warnings.push(format!(
+                "Album track redirect #{} for {} is shadowed by #{}.",
+                index + 1,
+                format!("{artist} - {title}").bright_blue(),
+                previous_index + 1,
+            ));
+        }
+    }
+
+    check_blacklisted_redirects("Artist equal redirect", &redirects.artists.equal_matches, &blacklist.artists, &mut warnings);
+    check_blacklisted_redirects("Title equal redirect", &redirects.titles.equal_matches, &blacklist.titles, &mut warnings);
+
+    for (kind, blacklist) in [("artist", &blacklist.artists), ("title", &blacklist.titles), ("difficulty", &blacklist.difficulties)] {
+        check_duplicate_entries(&format!("Blacklisted {kind}"), &blacklist.equal_matches, &mut warnings);
+
+        let patterns = blacklist.regex_matches.iter().map(|regex| regex.to_string()).collect::<Vec<String>>();
+        check_duplicate_entries(&format!("Blacklisted {kind} regex"), &patterns, &mut warnings);
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_errors, get_location, get_rule_warnings, get_unknown_key_warning};
+    use crate::config::Config;
+
+    const CONFIG: &str = r#"[scrobbler]
+user_id = "abc"
+min_beatmap_length_secs = -5
+scrobble_fails = "yes"
+typo = 1
+
+[scrobbler.musicbrainz]
+min_confidence = "high"
+
+[logger]
+level = "loud"
+
+[listenbrainz]
+user_token = "token"
+"#;
+
+    fn get_warnings(redirects: &str) -> Vec<String> {
+        colored::control::set_override(false);
+
+        let config = toml::from_str::<Config>(&format!("[scrobbler]\nuser_id = 1\n{redirects}\n[listenbrainz]\nuser_token = \"token\""));
+        get_rule_warnings(&config.unwrap())
+    }
+
+    #[test]
+    fn locate_keys() {
+        let segments = |path: &str| path.split('.').map(String::from).collect::<Vec<String>>();
+
+        assert_eq!(get_location(CONFIG, &segments("scrobbler.typo")), Some((5, 1)));
+        assert_eq!(get_location(CONFIG, &segments("scrobbler.musicbrainz.min_confidence")), Some((8, 1)));
+        assert_eq!(get_location(CONFIG, &segments("logger.level")), Some((11, 1)));
+        assert_eq!(get_location(CONFIG, &segments("logger.missing")), None);
+
+        colored::control::set_override(false);
+        assert_eq!(
+            get_unknown_key_warning(CONFIG, &segments("scrobbler.typo")),
+            "Unknown key scrobbler.typo at line 5, column 1 will be ignored."
+        );
+    }
+
+    #[test]
+    fn report_every_error() {
+        let (errors, unknown_keys) = get_errors(CONFIG);
+        let lines = errors.iter().map(|error| error.lines().next().unwrap_or_default()).collect::<Vec<&str>>();
+
+        assert_eq!(
+            lines,
+            [
+                "TOML parse error at line 2, column 11",
+                "TOML parse error at line 3, column 27",
+                "TOML parse error at line 4, column 18",
+                "TOML parse error at line 8, column 18",
+                "TOML parse error at line 11, column 9",
+            ],
+        );
+        assert_eq!(unknown_keys, [["scrobbler", "typo"]]);
+        assert_eq!(get_errors("[listenbrainz]\nuser_token = \"token\"").0, ["Missing [scrobbler] section."]);
+    }
+
+    #[test]
+    fn report_shadowed_equal_redirects() {
+        let warnings = get_warnings(
+            r#"[scrobbler.redirects.artists]
+equal_matches = [
+    ["a", "b"],
+    ["A", "c"],
+    ["d", "e"],
+    ["d", "e"],
+    { match = "f", replace = "g", when_mode = "osu" },
+    ["f", "h"],
+    { match = "f", replace = "i", when_mode = "osu" },
+]"#,
+        );
+
+        assert_eq!(
+            warnings,
+            [
+                "Artist equal redirect #2 for source a is shadowed by #1, which takes precedence for every score it applies to.",
+                "Artist equal redirect #4 duplicates #3 for source d.",
+                "Artist equal redirect #7 for source f is shadowed by #5, which takes precedence for every score it applies to.",
+            ],
+        );
+    }
+
+    #[test]
+    fn report_regex_redirects_behind_equal() {
+        let warnings = get_warnings(
+            r#"[scrobbler.redirects.titles]
+equal_matches = [["t", "u"], { match = "v", replace = "w", when_mode = "osu" }]
+regex_matches = [["^t$", "x"], ["^v$", "y"], { match = "^v$", replace = "z", when_mode = "osu" }]"#,
+        );
+
+        assert_eq!(
+            warnings,
+            [
+                "Title regex redirect #1 never applies to source t since equal redirect #1 takes precedence.",
+                "Title regex redirect #3 never applies to source v since equal redirect #2 takes precedence.",
+            ],
+        );
+    }
+}
@@ -0,0 +1,167 @@
+mod rotating_file;
+
+pub use rotating_file::RotatingFile;
+
+use crate::{
+    config::{Config, LogFormat, LogLevel, LoggerConfig, LoggerFileConfig},
+    utils::strip_ansi_codes,
+};
+use anyhow::Result;
+use chrono::{Local, SecondsFormat};
+use colored::{
+    Color, Colorize,
+    control::{set_override, unset_override},
+};
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    io::{IsTerminal, stdout},
+    sync::{LazyLock, Mutex, OnceLock, RwLock},
+};
+
+static CLI_FORMAT: OnceLock<LogFormat> = OnceLock::new();
+static CONFIG: LazyLock<RwLock<LoggerConfig>> = LazyLock::new(|| RwLock::new(LoggerConfig::default()));
+static FILE: Mutex<Option<(LoggerFileConfig, RotatingFile)>> = Mutex::new(None);
+
+thread_local! {
+    static FIELDS: RefCell<Vec<(&'static str, Value)>> = const { RefCell::new(vec![]) };
+}
+
+impl LogLevel {
+    fn get_color(&self) -> Color {
+        match self {
+            Self::Trace => Color::BrightBlack,
+            Self::Debug => Color::BrightCyan,
+            Self::Info => Color::BrightGreen,
+            Self::Warn => Color::BrightYellow,
+            Self::Error => Color::BrightRed,
+        }
+    }
+}
+
+// Removes its fields from the structured log output once dropped
+pub struct LoggerScope {
+    len: usize,
+}
+
+impl Drop for LoggerScope {
+    fn drop(&mut self) {
+        FIELDS.with_borrow_mut(|fields| fields.truncate(self.len));
+    }
+}
+
+pub struct Logger;
+
+impl Logger {
+    pub fn init(cli_format: Option<LogFormat>) {
+        if let Some(format) = cli_format {
+            _ = CLI_FORMAT.set(format);
+        }
+
+        Self::update_color_override();
+    }
+
+    pub fn configure(config: &LoggerConfig) -> Result<()> {
+        let mut file = FILE.lock().unwrap_or_else(|error| error.into_inner());
+
+        if file.as_ref().map(|(file_config, _)| file_config) != config.file.as_ref() {
+            *file = match config.file.as_ref() {
+                Some(file_config) => {
+                    let path = Config::get_data_path(&file_config.path)?;
+                    Some((file_config.clone(), RotatingFile::open(path, file_config.max_size_bytes, file_config.max_files, None)?))
+                },
+                None => None,
+            };
+        }
+
+        *CONFIG.write().unwrap_or_else(|error| error.into_inner()) = config.clone();
+        Self::update_color_override();
+
+        Ok(())
+    }
+
+    fn get_format() -> LogFormat {
+        CLI_FORMAT.get().copied().unwrap_or_else(|| CONFIG.read().unwrap_or_else(|error| error.into_inner()).format)
+    }
+
+    fn update_color_override() {
+        if Self::get_format() == LogFormat::Json || !stdout().is_terminal() {
+            set_override(false);
+        } else {
+            unset_override();
+        }
+    }
+
+    fn is_enabled(level: LogLevel, tag: &str) -> bool {
+        let config = CONFIG.read().unwrap_or_else(|error| error.into_inner());
+        level >= config.tags.get(&tag.to_lowercase()).copied().unwrap_or(config.level)
+    }
+
+    pub fn scope<const N: usize>(new_fields: [(&'static str, Value); N]) -> LoggerScope {
+        FIELDS.with_borrow_mut(|fields| {
+            let len = fields.len();
+            fields.extend(new_fields);
+            LoggerScope { len }
+        })
+    }
+
+    fn log<T: Display>(level: LogLevel, tag: &str, message: T, is_sub: bool) {
+        if !Self::is_enabled(level, tag) {
+            return;
+        }
+
+        let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+
+        let line = if Self::get_format() == LogFormat::Json {
+            let mut event = Map::new();
+            event.insert("timestamp".into(), timestamp.into());
+            event.insert("level".into(), level.to_string().into());
+            event.insert("tag".into(), tag.into());
+            event.insert("message".into(), message.to_string().into());
+
+            FIELDS.with_borrow(|fields| {
+                for (key, value) in fields {
+                    event.insert(key.to_string(), value.clone());
+                }
+            });
+
+            if level == LogLevel::Error {
+                event.insert("error".into(), message.to_string().into());
+            }
+
+            Value::Object(event).to_string()
+        } else {
+            let tag = if is_sub { format!("\t[{tag}]") } else { format!("[{tag}]") };
+            format!("{} {} {message}", timestamp.bright_black(), tag.color(level.get_color()))
+        };
+
+        println!("{line}");
+
+        if let Some((_, file)) = FILE.lock().unwrap_or_else(|error| error.into_inner()).as_mut()
+            && let Err(error) = file.write_line(&strip_ansi_codes(&line))
+        {
+            eprintln!("{error:?}");
+        }
+    }
+
+    pub fn trace<T: Display>(tag: &str, message: T, is_sub: bool) {
+        Self::log(LogLevel::Trace, tag, message, is_sub);
+    }
+
+    pub fn debug<T: Display>(tag: &str, message: T, is_sub: bool) {
+        Self::log(LogLevel::Debug, tag, message, is_sub);
+    }
+
+    pub fn error<T: Display>(tag: &str, message: T, is_sub: bool) {
+        Self::log(LogLevel::Error, tag, message, is_sub);
+    }
+
+    pub fn success<T: Display>(tag: &str, message: T, is_sub: bool) {
+        Self::log(LogLevel::Info, tag, message, is_sub);
+    }
+
+    pub fn warn<T: Display>(tag: &str, message: T, is_sub: bool) {
+        Self::log(LogLevel::Warn, tag, message, is_sub);
+    }
+}
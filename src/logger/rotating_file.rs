@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{File, OpenOptions, create_dir_all, remove_file, rename},
+    io::Write,
+    path::PathBuf,
+};
+
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size_bytes: u64,
+    max_files: u32,
+    header: Option<String>,
+}
+
+impl RotatingFile {
+    pub fn open(path: PathBuf, max_size_bytes: u64, max_files: u32, header: Option<String>) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).with_context(|| format!("Could not create directory {}.", parent.to_string_lossy()))?;
+        }
+
+        let file = Self::open_file(&path)?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        let mut rotating_file = Self { path, file, size, max_size_bytes, max_files, header };
+        rotating_file.write_header()?;
+
+        Ok(rotating_file)
+    }
+
+    // Headers are written at the start of every new file so that rotated files stay valid on their own
+    fn write_header(&mut self) -> Result<()> {
+        let Some(header) = self.header.as_ref().filter(|_| self.size == 0) else { return Ok(()) };
+        let header = format!("{header}\n");
+
+        self.file.write_all(header.as_bytes()).with_context(|| format!("Could not write to {}.", self.path.to_string_lossy()))?;
+        self.size += header.len() as u64;
+
+        Ok(())
+    }
+
+    fn open_file(path: &PathBuf) -> Result<File> {
+        OpenOptions::new().create(true).append(true).open(path).with_context(|| format!("Could not open {}.", path.to_string_lossy()))
+    }
+
+    fn get_rotated_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    pub fn rotate(&mut self) -> Result<()> {
+        // Shifts file.log.1 to file.log.2 and so on, dropping the oldest file once the retention count is reached
+        if self.max_files == 0 {
+            remove_file(&self.path).ok();
+        } else {
+            remove_file(self.get_rotated_path(self.max_files)).ok();
+
+            for index in (1..self.max_files).rev() {
+                rename(self.get_rotated_path(index), self.get_rotated_path(index + 1)).ok();
+            }
+
+            rename(&self.path, self.get_rotated_path(1)).with_context(|| format!("Could not rotate {}.", self.path.to_string_lossy()))?;
+        }
+
+        self.file = Self::open_file(&self.path)?;
+        self.size = 0;
+
+        self.write_header()
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        let line = format!("{line}\n");
+
+        if self.max_size_bytes > 0
+            && self.size > self.header.as_ref().map_or(0, |header| header.len() as u64 + 1)
+            && self.size + line.len() as u64 > self.max_size_bytes
+        {
+            self.rotate()?;
+        }
+
+        self.file.write_all(line.as_bytes()).with_context(|| format!("Could not write to {}.", self.path.to_string_lossy()))?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+}
@@ -1,16 +1,59 @@
+mod api;
 mod config;
 mod logger;
+mod metrics;
+mod musicbrainz;
+mod presets;
 mod scores;
 mod scrobbler;
 mod utils;
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use config::{Config, LogFormat};
 use logger::Logger;
-use scrobbler::Scrobbler;
-use std::{io::stdin, process::exit};
+use musicbrainz::MusicBrainzCache;
+use scrobbler::{LastfmScrobbler, Scrobbler};
+use serde_json::{Value, from_value};
+use std::{env::args, io::stdin, process::exit};
+
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let prefix = format!("{name}=");
+    let Some(index) = args.iter().position(|arg| *arg == name || arg.starts_with(&prefix)) else { return Ok(None) };
+    let arg = args.remove(index);
+
+    if let Some(value) = arg.strip_prefix(&prefix) {
+        return Ok(Some(value.to_string()));
+    }
+
+    if index >= args.len() {
+        bail!("Missing value for {name}.");
+    }
+
+    Ok(Some(args.remove(index)))
+}
+
+fn run(mut args: Vec<String>) -> Result<()> {
+    let log_format = take_option(&mut args, "--log-format")?
+        .map(|log_format| from_value::<LogFormat>(Value::String(log_format)).context("Invalid log format. Expected pretty or json."))
+        .transpose()?;
+
+    Logger::init(log_format);
+
+    match args.first().map(String::as_str) {
+        Some("auth") => match args.get(1).map(String::as_str) {
+            Some("lastfm") => LastfmScrobbler::run_auth_command(),
+            _ => bail!("Unknown auth command. Expected lastfm."),
+        },
+        Some("cache") => MusicBrainzCache::run_command(&args[1..]),
+        Some("check-config") => Config::run_check_command(),
+        _ => Scrobbler::new().and_then(|mut scrobbler| scrobbler.start()),
+    }
+}
 
 fn main() -> Result<()> {
-    if let Err(error) = Scrobbler::new().and_then(|mut scrobbler| scrobbler.start()) {
+    let result = run(args().skip(1).collect());
+
+    if let Err(error) = result {
         Logger::error("Scrobbler", format!("{error:?}"), false);
         println!("\nPress enter to exit.");
         let _ = stdin().read_line(&mut String::new());
@@ -0,0 +1,143 @@
+use crate::{config::MetricsConfig, logger::Logger};
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{LazyLock, Mutex},
+    thread::spawn,
+    time::Duration,
+};
+use tiny_http::{Header, Method, Response, Server};
+
+const LATENCY_BUCKETS_SECS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1., 2.5, 5., 10.];
+
+static STATE: LazyLock<Mutex<MetricsState>> = LazyLock::new(|| Mutex::new(MetricsState::default()));
+
+#[derive(Default)]
+struct MetricsState {
+    polls: u64,
+    osu_api_errors: BTreeMap<String, u64>,
+    scores_seen: u64,
+    scores_skipped: BTreeMap<&'static str, u64>,
+    scrobbles: BTreeMap<(&'static str, &'static str), u64>,
+    musicbrainz_lookups: BTreeMap<&'static str, u64>,
+    musicbrainz_latency_buckets: [u64; LATENCY_BUCKETS_SECS.len()],
+    musicbrainz_latency_sum_secs: f64,
+    musicbrainz_latency_count: u64,
+}
+
+pub struct Metrics;
+
+impl Metrics {
+    fn update<F: FnOnce(&mut MetricsState)>(f: F) {
+        f(&mut STATE.lock().unwrap_or_else(|error| error.into_inner()));
+    }
+
+    pub fn record_poll() {
+        Self::update(|state| state.polls += 1);
+    }
+
+    pub fn record_osu_api_error(status_code: Option<u16>) {
+        let status_code = status_code.map(|status_code| status_code.to_string()).unwrap_or_else(|| "none".into());
+        Self::update(|state| *state.osu_api_errors.entry(status_code).or_default() += 1);
+    }
+
+    pub fn record_score_seen() {
+        Self::update(|state| state.scores_seen += 1);
+    }
+
+    pub fn record_skip(reason: &'static str) {
+        Self::update(|state| *state.scores_skipped.entry(reason).or_default() += 1);
+    }
+
+    pub fn record_scrobble(backend: &'static str, succeeded: bool) {
+        let result = if succeeded { "success" } else { "failure" };
+        Self::update(|state| *state.scrobbles.entry((backend, result)).or_default() += 1);
+    }
+
+    pub fn record_musicbrainz_lookup(result: &'static str) {
+        Self::update(|state| *state.musicbrainz_lookups.entry(result).or_default() += 1);
+    }
+
+    pub fn record_musicbrainz_latency(duration: Duration) {
+        let secs = duration.as_secs_f64();
+
+        Self::update(|state| {
+            for (index, bucket) in LATENCY_BUCKETS_SECS.iter().enumerate() {
+                if secs <= *bucket {
+                    state.musicbrainz_latency_buckets[index] += 1;
+                }
+            }
+
+            state.musicbrainz_latency_sum_secs += secs;
+            state.musicbrainz_latency_count += 1;
+        });
+    }
+
+    fn render() -> String {
+        let state = STATE.lock().unwrap_or_else(|error| error.into_inner());
+        let mut output = String::new();
+
+        let write_header = |output: &mut String, name: &str, kind: &str, help: &str| {
+            _ = writeln!(output, "# HELP osu_scrobbler_{name} {help}\n# TYPE osu_scrobbler_{name} {kind}");
+        };
+
+        write_header(&mut output, "polls_total", "counter", "Number of polls for the user's recent scores.");
+        _ = writeln!(output, "osu_scrobbler_polls_total {}", state.polls);
+
+        write_header(&mut output, "osu_api_errors_total", "counter", "Number of failed osu! API requests by status code.");
+        for (status_code, count) in &state.osu_api_errors {
+            _ = writeln!(output, "osu_scrobbler_osu_api_errors_total{{status_code=\"{status_code}\"}} {count}");
+        }
+
+        write_header(&mut output, "scores_seen_total", "counter", "Number of new scores seen.");
+        _ = writeln!(output, "osu_scrobbler_scores_seen_total {}", state.scores_seen);
+
+        write_header(&mut output, "scores_skipped_total", "counter", "Number of scores skipped by reason.");
+        for (reason, count) in &state.scores_skipped {
+            _ = writeln!(output, "osu_scrobbler_scores_skipped_total{{reason=\"{reason}\"}} {count}");
+        }
+
+        write_header(&mut output, "scrobbles_total", "counter", "Number of scrobbles by backend and result.");
+        for ((backend, result), count) in &state.scrobbles {
+            _ = writeln!(output, "osu_scrobbler_scrobbles_total{{backend=\"{backend}\",result=\"{result}\"}} {count}");
+        }
+
+        write_header(&mut output, "musicbrainz_lookups_total", "counter", "Number of MusicBrainz lookups by result.");
+        for (result, count) in &state.musicbrainz_lookups {
+            _ = writeln!(output, "osu_scrobbler_musicbrainz_lookups_total{{result=\"{result}\"}} {count}");
+        }
+
+        write_header(&mut output, "musicbrainz_lookup_duration_seconds", "histogram", "Duration of MusicBrainz lookups.");
+        for (bucket, count) in LATENCY_BUCKETS_SECS.iter().zip(state.musicbrainz_latency_buckets) {
+            _ = writeln!(output, "osu_scrobbler_musicbrainz_lookup_duration_seconds_bucket{{le=\"{bucket}\"}} {count}");
+        }
+        _ = writeln!(output, "osu_scrobbler_musicbrainz_lookup_duration_seconds_bucket{{le=\"+Inf\"}} {}", state.musicbrainz_latency_count);
+        _ = writeln!(output, "osu_scrobbler_musicbrainz_lookup_duration_seconds_sum {}", state.musicbrainz_latency_sum_secs);
+        _ = writeln!(output, "osu_scrobbler_musicbrainz_lookup_duration_seconds_count {}", state.musicbrainz_latency_count);
+
+        output
+    }
+
+    pub fn serve(config: &MetricsConfig) -> Result<()> {
+        let server = Server::http(&config.address).map_err(|error| anyhow!("Could not start metrics server: {error}"))?;
+
+        Logger::success("Metrics", format!("Listening on {}.", format!("http://{}/metrics", config.address).bright_blue()), false);
+
+        spawn(move || {
+            for request in server.incoming_requests() {
+                let response = if *request.method() == Method::Get && request.url() == "/metrics" {
+                    let content_type = Header::from_bytes("content-type", "text/plain; version=0.0.4").unwrap();
+                    Response::from_string(Self::render()).with_header(content_type)
+                } else {
+                    Response::from_string("Not Found").with_status_code(404)
+                };
+
+                _ = request.respond(response);
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    config::{Config, ScrobblerMusicBrainzConfig},
+    logger::Logger,
+    metrics::Metrics,
+    musicbrainz::MusicBrainzRelease,
+    scores::Score,
+};
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, to_string_pretty};
+use std::{
+    collections::BTreeMap,
+    fs::{read_to_string, write},
+    path::PathBuf,
+    time::Instant,
+};
+
+const CACHE_FILE_NAME: &str = "musicbrainz_cache.json";
+
+#[derive(Debug)]
+pub struct MusicBrainzCache {
+    path: PathBuf,
+    entries: BTreeMap<String, MusicBrainzCacheEntry>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct MusicBrainzCacheEntry {
+    fetched_at: i64,
+    release: Option<MusicBrainzRelease>,
+}
+
+impl MusicBrainzCache {
+    pub fn load() -> Result<Self> {
+        let path = Config::get_data_path(CACHE_FILE_NAME)?;
+        let mut entries = BTreeMap::new();
+
+        if path.exists() {
+            let cache = read_to_string(&path)
+                .context("An error occurred while trying to read MusicBrainz cache file.")
+                .and_then(|cache_string| from_str(&cache_string).context("An error occurred while parsing MusicBrainz cache file."));
+
+            match cache {
+                Ok(cache) => entries = cache,
+                Err(error) => Logger::warn("MusicBrainz", format!("{error:?}"), false),
+            }
+        }
+
+        Ok(Self { path, entries })
+    }
+
+    fn save(&self) -> Result<()> {
+        write(&self.path, to_string_pretty(&self.entries)?).context("An error occurred while trying to write MusicBrainz cache file.")
+    }
+
+    // Entries are otherwise only replaced when the same key is looked up again, so expired ones would pile up
+    fn prune(&mut self, config: &ScrobblerMusicBrainzConfig, now: i64) {
+        let max_ttl_secs = config.cache_ttl_secs.max(config.cache_negative_ttl_secs) as i64;
+        self.entries.retain(|_, entry| now - entry.fetched_at < max_ttl_secs);
+    }
+
+    // The minimum confidence is part of the key since changing it can turn a cached hit into a miss and vice versa
+    fn get_key(score: &Score, artist: &str, title: &str, config: &ScrobblerMusicBrainzConfig) -> String {
+        let normalize = |string: &str| string.to_lowercase().split_whitespace().collect::<Vec<&str>>().join(" ");
+        format!("{}:{}:{}:{}", score.beatmapset.id, normalize(artist), normalize(title), config.min_confidence)
+    }
+
+    pub fn get_release(
+        &mut self,
+        score: &Score,
+        artist: &str,
+        title: &str,
+        config: &ScrobblerMusicBrainzConfig,
+    ) -> Option<MusicBrainzRelease> {
+        if !config.cache {
+            return Self::fetch_release(score, artist, title, config).ok().flatten();
+        }
+
+        let key = Self::get_key(score, artist, title, config);
+        let now = Utc::now().timestamp();
+
+        if let Some(entry) = self.entries.get(&key) {
+            let ttl_secs = if entry.release.is_some() { config.cache_ttl_secs } else { config.cache_negative_ttl_secs };
+
+            if now - entry.fetched_at < ttl_secs as i64 {
+                Metrics::record_musicbrainz_lookup("cache_hit");
+                return entry.release.clone();
+            }
+        }
+
+        let release = Self::fetch_release(score, artist, title, config).ok()?;
+        self.entries.insert(key, MusicBrainzCacheEntry { fetched_at: now, release: release.clone() });
+        self.prune(config, now);
+
+        if let Err(error) = self.save() {
+            Logger::warn("MusicBrainz", format!("{error:?}"), true);
+        }
+
+        release
+    }
+
+    fn fetch_release(score: &Score, artist: &str, title: &str, config: &ScrobblerMusicBrainzConfig) -> Result<Option<MusicBrainzRelease>> {
+        let started_at = Instant::now();
+        let release = score.get_musicbrainz_release(artist, title, config);
+
+        Metrics::record_musicbrainz_latency(started_at.elapsed());
+        Metrics::record_musicbrainz_lookup(match &release {
+            Ok(Some(_)) => "matched",
+            Ok(None) => "not_matched",
+            Err(_) => "error",
+        });
+
+        release.inspect_err(|error| {
+            Logger::warn("MusicBrainz", format!("Could not look up album name: {error:?}"), true);
+        })
+    }
+
+    pub fn run_command(args: &[String]) -> Result<()> {
+        let mut cache = Self::load()?;
+        let filter = args.get(1).map(|filter| filter.to_lowercase());
+        let keys =
+            cache.entries.keys().filter(|key| filter.as_ref().is_none_or(|filter| key.contains(filter))).cloned().collect::<Vec<String>>();
+
+        match args.first().map(String::as_str) {
+            Some("list") => {
+                for key in &keys {
+                    let entry = &cache.entries[key];
+                    let fetched_at = DateTime::from_timestamp(entry.fetched_at, 0).unwrap_or_default().to_rfc3339();
+                    let release = match &entry.release {
+                        Some(release) => format!(
+                            "{} (release {}, recording {})",
+                            release.title.bright_blue(),
+                            release.mbid.bright_blue(),
+                            release.recording_mbid.bright_blue(),
+                        ),
+                        None => "No release found".bright_black().to_string(),
+                    };
+
+                    Logger::success(
+                        "MusicBrainz",
+                        format!("{} - {release} - fetched at {}", key.bright_blue(), fetched_at.bright_blue()),
+                        false,
+                    );
+                }
+
+                Logger::success("MusicBrainz", format!("Found {} cache entries.", keys.len().to_string().bright_blue()), false);
+            },
+            Some("clear") => {
+                for key in &keys {
+                    cache.entries.remove(key);
+                }
+
+                cache.save()?;
+                Logger::success(
+                    "MusicBrainz",
+                    format!("Successfully cleared {} cache entries.", keys.len().to_string().bright_blue()),
+                    false,
+                );
+            },
+            _ => bail!("Usage: osu-scrobbler cache <list|clear> [filter]"),
+        }
+
+        Ok(())
+    }
+}
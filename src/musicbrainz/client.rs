@@ -0,0 +1,94 @@
+use crate::{
+    config::ScrobblerMusicBrainzConfig,
+    musicbrainz::{RecordingSearchResult, ScoredRecording},
+};
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use reqwest::{StatusCode, blocking::Client, header::RETRY_AFTER};
+use std::{
+    sync::{LazyLock, Mutex},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+static REQWEST: LazyLock<Client> = LazyLock::new(Client::new);
+
+// MusicBrainz allows an average of one request per second per client
+static LAST_REQUEST_AT: Mutex<Option<Instant>> = Mutex::new(None);
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RETRIES: u32 = 3;
+// Lookups run on the polling thread, so a longer wait is not worth blocking it for
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+pub struct MusicBrainzClient<'a> {
+    config: &'a ScrobblerMusicBrainzConfig,
+}
+
+impl<'a> MusicBrainzClient<'a> {
+    pub fn new(config: &'a ScrobblerMusicBrainzConfig) -> Self {
+        Self { config }
+    }
+
+    fn get_user_agent(&self) -> String {
+        format!("osu-scrobbler/{} ( {} )", env!("CARGO_PKG_VERSION"), self.config.contact)
+    }
+
+    fn wait_for_rate_limit() {
+        let mut last_request_at = LAST_REQUEST_AT.lock().unwrap_or_else(|error| error.into_inner());
+
+        if let Some(elapsed) = last_request_at.map(|last_request_at| last_request_at.elapsed())
+            && elapsed < REQUEST_INTERVAL
+        {
+            sleep(REQUEST_INTERVAL - elapsed);
+        }
+
+        *last_request_at = Some(Instant::now());
+    }
+
+    pub fn search_recordings(&self, query: &str) -> Result<Vec<ScoredRecording>> {
+        let url = format!("{}/ws/2/recording", self.config.server_url.trim_end_matches('/'));
+        let query = query.strip_prefix("query=").unwrap_or(query);
+        let mut retries = 0;
+
+        loop {
+            Self::wait_for_rate_limit();
+
+            let response = REQWEST
+                .get(&url)
+                .header("user-agent", self.get_user_agent())
+                .query(&[("query", query), ("limit", "100"), ("fmt", "json")])
+                .send()
+                .context("Could not send request to search MusicBrainz recordings.")?;
+
+            let status_code = response.status();
+
+            if status_code == StatusCode::SERVICE_UNAVAILABLE && retries < MAX_RETRIES {
+                let retry_after_secs = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|retry_after| retry_after.to_str().ok())
+                    .and_then(|retry_after| retry_after.parse::<u64>().ok())
+                    .unwrap_or(1);
+                let retry_after = Duration::from_secs(retry_after_secs);
+
+                if retry_after > MAX_RETRY_AFTER {
+                    bail!(
+                        "Could not search MusicBrainz recordings. Service is unavailable for {} seconds.",
+                        retry_after_secs.to_string().bright_blue(),
+                    );
+                }
+
+                sleep(retry_after);
+                retries += 1;
+                continue;
+            }
+
+            if status_code != StatusCode::OK {
+                bail!("Could not search MusicBrainz recordings. Received status code: {}", status_code.as_str().bright_blue());
+            }
+
+            let results = response.json::<RecordingSearchResult>().context("Could not deserialize MusicBrainz recordings.")?;
+            return Ok(results.recordings);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{musicbrainz::ScoredRecording, scores::Score};
+use musicbrainz_rs::entity::recording::Recording;
+use regex::Regex;
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::LazyLock,
+};
+
+static CUT_VERSION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(tv|short|cut|game|movie|anime)[\s.-]*(size|ver\.?|version|edit|edition)\b|\bcut\b").unwrap());
+
+const SEARCH_WEIGHT: f64 = 0.3;
+const LENGTH_WEIGHT: f64 = 0.3;
+const ARTIST_WEIGHT: f64 = 0.3;
+const RELEASE_DATE_WEIGHT: f64 = 0.1;
+
+// Used when the beatmap is a cut version and the recording is noticeably longer, since the lengths can't be compared directly
+const CUT_VERSION_LENGTH_SCORE: f64 = 0.75;
+const UNKNOWN_LENGTH_SCORE: f64 = 0.5;
+
+pub struct RecordingMatch<'a> {
+    pub recording: &'a Recording,
+    pub confidence: f64,
+    search: f64,
+    length: f64,
+    artist: f64,
+    release_date: f64,
+}
+
+impl<'a> RecordingMatch<'a> {
+    // Returns every candidate, ordered from the highest confidence to the lowest
+    pub fn get_ranked(recordings: &'a [ScoredRecording], score: &Score, artist: &str) -> Vec<Self> {
+        let release_years = recordings.iter().filter_map(|recording| get_first_release_year(&recording.recording)).collect::<Vec<u32>>();
+        let (min_release_year, max_release_year) = (release_years.iter().min().copied(), release_years.iter().max().copied());
+        let is_cut_version =
+            [&score.beatmapset.title, &score.beatmapset.title_unicode].iter().any(|title| CUT_VERSION_REGEX.is_match(title));
+        let artists = [artist, &score.beatmapset.artist, &score.beatmapset.artist_unicode];
+
+        let mut matches = recordings
+            .iter()
+            .map(|scored_recording| {
+                let recording = &scored_recording.recording;
+                let search = scored_recording.score as f64 / 100.;
+
+                let length = match recording.length {
+                    Some(length_ms) => {
+                        let recording_secs = length_ms as f64 / 1000.;
+                        let beatmap_secs = score.beatmap.total_length as f64;
+                        let tolerance_secs = (beatmap_secs * 0.2).max(15.);
+
+                        if is_cut_version && recording_secs > beatmap_secs + tolerance_secs {
+                            CUT_VERSION_LENGTH_SCORE
+                        } else {
+                            (1. - (recording_secs - beatmap_secs).abs() / tolerance_secs).max(0.)
+                        }
+                    },
+                    None => UNKNOWN_LENGTH_SCORE,
+                };
+
+                let artist_credit = get_artist_credit(recording);
+                let artist = artists.iter().map(|artist| get_similarity(artist, &artist_credit)).fold(0., f64::max);
+
+                let release_date = match (get_first_release_year(recording), min_release_year, max_release_year) {
+                    (Some(year), Some(min), Some(max)) if max > min => 1. - (year - min) as f64 / (max - min) as f64,
+                    (Some(_), _, _) => 1.,
+                    _ => 0.,
+                };
+
+                let confidence =
+                    search * SEARCH_WEIGHT + length * LENGTH_WEIGHT + artist * ARTIST_WEIGHT + release_date * RELEASE_DATE_WEIGHT;
+
+                Self { recording, confidence, search, length, artist, release_date }
+            })
+            .collect::<Vec<Self>>();
+
+        matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        matches
+    }
+
+    pub fn get_artist_credit(&self) -> String {
+        get_artist_credit(self.recording)
+    }
+}
+
+impl Display for RecordingMatch<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "confidence {:.2}: search {:.2}, length {:.2}, artist {:.2}, release date {:.2}",
+            self.confidence, self.search, self.length, self.artist, self.release_date,
+        )
+    }
+}
+
+fn get_artist_credit(recording: &Recording) -> String {
+    recording
+        .artist_credit
+        .iter()
+        .flatten()
+        .map(|artist_credit| format!("{}{}", artist_credit.name, artist_credit.joinphrase.as_deref().unwrap_or_default()))
+        .collect()
+}
+
+fn get_first_release_year(recording: &Recording) -> Option<u32> {
+    recording.first_release_date.as_ref().and_then(|date| date.0.get(..4)).and_then(|year| year.parse().ok())
+}
+
+// Sørensen-Dice coefficient over character bigrams, which also works for scripts that don't separate words with whitespace
+fn get_similarity(a: &str, b: &str) -> f64 {
+    let normalize = |string: &str| string.to_lowercase().chars().filter(|char| char.is_alphanumeric()).collect::<Vec<char>>();
+    let (a, b) = (normalize(a), normalize(b));
+
+    if a.len() < 2 || b.len() < 2 {
+        return if a == b { 1. } else { 0. };
+    }
+
+    let mut b_bigrams = b.windows(2).collect::<Vec<&[char]>>();
+    let a_bigrams_len = a.len() - 1;
+    let b_bigrams_len = b_bigrams.len();
+    let mut matches = 0;
+
+    for a_bigram in a.windows(2) {
+        if let Some(index) = b_bigrams.iter().position(|b_bigram| *b_bigram == a_bigram) {
+            b_bigrams.swap_remove(index);
+            matches += 1;
+        }
+    }
+
+    (2 * matches) as f64 / (a_bigrams_len + b_bigrams_len) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordingMatch, get_similarity};
+    use crate::{musicbrainz::ScoredRecording, scores::Score};
+    use serde_json::{from_value, json};
+
+    fn get_score(title: &str, total_length: u32) -> Score {
+        from_value(json!({
+            "id": 1,
+            "passed": true,
+            "mods": [],
+            "started_at": null,
+            "ended_at": "2026-01-01T00:00:00Z",
+            "beatmap": { "version": "Insane", "total_length": total_length, "hit_length": total_length },
+            "beatmapset": {
+                "id": 1,
+                "artist": "LiSA",
+                "artist_unicode": "LiSA",
+                "title": title,
+                "title_unicode": title,
+                "creator": "mapper",
+            },
+            "user": { "id": 1, "username": "player" },
+        }))
+        .unwrap()
+    }
+
+    fn get_recording(id: &str, artist: &str, length_ms: u32, first_release_date: &str) -> ScoredRecording {
+        from_value(json!({
+            "score": 100,
+            "id": id,
+            "title": "Gurenge",
+            "length": length_ms,
+            "first-release-date": first_release_date,
+            "artist-credit": [{ "name": artist, "artist": { "id": "artist", "name": artist, "sort-name": artist } }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn similarity() {
+        assert_eq!(get_similarity("LiSA", "lisa"), 1.);
+        assert_eq!(get_similarity("Simon & Garfunkel", "Simon and Garfunkel"), 2. * 12. / (13. + 16.));
+        assert_eq!(get_similarity("Yoasobi", "Aimer"), 0.);
+        assert_eq!(get_similarity("ア", "ア"), 1.);
+        assert_eq!(get_similarity("ア", "イ"), 0.);
+        assert_eq!(get_similarity("米津玄師", "米津 玄師"), 1.);
+    }
+
+    #[test]
+    fn rank_by_length_and_artist() {
+        let recordings = [
+            get_recording("wrong-artist", "Someone Else", 240_000, "2019"),
+            get_recording("wrong-length", "LiSA", 260_000, "2019"),
+            get_recording("best", "LiSA", 240_000, "2019"),
+        ];
+        let ranked = RecordingMatch::get_ranked(&recordings, &get_score("Gurenge", 240), "LiSA");
+        let ids = ranked.iter().map(|recording_match| recording_match.recording.id.as_str()).collect::<Vec<&str>>();
+
+        assert_eq!(ids, ["best", "wrong-length", "wrong-artist"]);
+        assert!(ranked.windows(2).all(|pair| pair[0].confidence >= pair[1].confidence));
+    }
+
+    #[test]
+    fn rank_full_version_for_cut_beatmap() {
+        let recordings = [get_recording("unrelated", "LiSA", 30_000, "2019"), get_recording("full", "LiSA", 240_000, "2019")];
+        let ranked = RecordingMatch::get_ranked(&recordings, &get_score("Gurenge (TV Size)", 90), "LiSA");
+
+        assert_eq!(ranked[0].recording.id, "full");
+    }
+
+    #[test]
+    fn rank_earlier_release_first() {
+        let recordings = [get_recording("cover", "LiSA", 240_000, "2023"), get_recording("original", "LiSA", 240_000, "2019-07-03")];
+        let ranked = RecordingMatch::get_ranked(&recordings, &get_score("Gurenge", 240), "LiSA");
+
+        assert_eq!(ranked[0].recording.id, "original");
+    }
+}
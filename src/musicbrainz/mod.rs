@@ -0,0 +1,44 @@
+mod cache;
+mod client;
+mod matching;
+
+pub use cache::MusicBrainzCache;
+pub use client::MusicBrainzClient;
+pub use matching::RecordingMatch;
+
+use musicbrainz_rs::entity::{recording::Recording, release::Release};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MusicBrainzRelease {
+    pub title: String,
+    pub mbid: String,
+    pub recording_mbid: String,
+
+    #[serde(default)]
+    pub artist_mbids: Vec<String>,
+}
+
+impl MusicBrainzRelease {
+    pub fn new(release: &Release, recording: &Recording) -> Self {
+        Self {
+            title: release.title.clone(),
+            mbid: release.id.clone(),
+            recording_mbid: recording.id.clone(),
+            artist_mbids: recording.artist_credit.iter().flatten().map(|artist_credit| artist_credit.artist.id.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecordingSearchResult {
+    pub recordings: Vec<ScoredRecording>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScoredRecording {
+    pub score: u8,
+
+    #[serde(flatten)]
+    pub recording: Recording,
+}
@@ -0,0 +1,175 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    sync::LazyLock,
+};
+
+// Matches a suffix wrapped in parentheses or brackets (including full-width ones) along with its leading whitespace
+fn build_suffix_regex(inner: &str) -> Regex {
+    Regex::new(&format!(r"(?i)\s*[(\[（［]\s*{inner}\s*[)\]）］]")).unwrap()
+}
+
+static TV_SIZE_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"tv[\s.-]*(size|ver\.?|version|edit)(\s*ver\.?)?"));
+static CUT_VER_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"[^()\[\]（）［］]*\bcut\b[^()\[\]（）［］]*"));
+static SHORT_VER_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"short[\s.-]*(ver\.?|version|edit)"));
+static SPED_UP_VER_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"sped[\s-]*up(\s*(ver\.?|version|edit))?"));
+static NIGHTCORE_MIX_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"nightcore(\s*(mix|ver\.?|version|edit))?"));
+static EXTENDED_REGEX: LazyLock<Regex> = LazyLock::new(|| build_suffix_regex(r"extended(\s*(ver\.?|version|mix|edit))?"));
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy, Debug)]
+pub enum RedirectPreset {
+    #[serde(rename = "strip_tv_size")]
+    TvSize,
+
+    #[serde(rename = "strip_cut_ver")]
+    CutVer,
+
+    #[serde(rename = "strip_short_ver")]
+    ShortVer,
+
+    #[serde(rename = "strip_sped_up_ver")]
+    SpedUpVer,
+
+    #[serde(rename = "strip_nightcore_mix")]
+    NightcoreMix,
+
+    #[serde(rename = "strip_extended")]
+    Extended,
+}
+
+impl RedirectPreset {
+    fn get_regex(&self) -> &'static Regex {
+        match self {
+            Self::TvSize => &TV_SIZE_REGEX,
+            Self::CutVer => &CUT_VER_REGEX,
+            Self::ShortVer => &SHORT_VER_REGEX,
+            Self::SpedUpVer => &SPED_UP_VER_REGEX,
+            Self::NightcoreMix => &NIGHTCORE_MIX_REGEX,
+            Self::Extended => &EXTENDED_REGEX,
+        }
+    }
+
+    pub fn apply(&self, title: &str) -> Option<String> {
+        let regex = self.get_regex();
+
+        if !regex.is_match(title) {
+            return None;
+        }
+
+        let new_title = regex.replace_all(title, "").split_whitespace().collect::<Vec<&str>>().join(" ");
+        if new_title.is_empty() { None } else { Some(new_title) }
+    }
+}
+
+impl Display for RedirectPreset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let name = match self {
+            Self::TvSize => "strip_tv_size",
+            Self::CutVer => "strip_cut_ver",
+            Self::ShortVer => "strip_short_ver",
+            Self::SpedUpVer => "strip_sped_up_ver",
+            Self::NightcoreMix => "strip_nightcore_mix",
+            Self::Extended => "strip_extended",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RedirectPreset;
+
+    fn assert_preset(preset: RedirectPreset, samples: &[(&str, &str)]) {
+        for (title, expected) in samples {
+            assert_eq!(preset.apply(title).as_deref().unwrap_or(title), *expected, "{preset} on {title:?}");
+        }
+    }
+
+    #[test]
+    fn strip_tv_size() {
+        assert_preset(
+            RedirectPreset::TvSize,
+            &[
+                ("Unravel (TV Size)", "Unravel"),
+                ("Gurenge (TV size ver.)", "Gurenge"),
+                ("Silhouette (TV Ver.)", "Silhouette"),
+                ("Kaikai Kitan [TV Size]", "Kaikai Kitan"),
+                ("Idol (TV-size)", "Idol"),
+                ("残酷な天使のテーゼ （TV Size）", "残酷な天使のテーゼ"),
+                ("Zankoku na Tenshi no Te-ze (TV Edit)", "Zankoku na Tenshi no Te-ze"),
+                ("TV Size", "TV Size"),
+                ("TVXQ (Remix)", "TVXQ (Remix)"),
+            ],
+        );
+    }
+
+    #[test]
+    fn strip_cut_ver() {
+        assert_preset(
+            RedirectPreset::CutVer,
+            &[
+                ("Freedom Dive (Cut Ver.)", "Freedom Dive"),
+                ("Blue Zenith (Game Cut)", "Blue Zenith"),
+                ("Sidetracked Day (Short Cut Ver.)", "Sidetracked Day"),
+                ("Harumachi Clover [Cut]", "Harumachi Clover"),
+                ("The Big Black (Sped Up & Cut Ver.)", "The Big Black"),
+                ("Cutie Panther", "Cutie Panther"),
+                ("Shortcut (Original Mix)", "Shortcut (Original Mix)"),
+            ],
+        );
+    }
+
+    #[test]
+    fn strip_short_ver() {
+        assert_preset(
+            RedirectPreset::ShortVer,
+            &[
+                ("Night of Nights (Short Ver.)", "Night of Nights"),
+                ("Bad Apple!! (Short Version)", "Bad Apple!!"),
+                ("Bad Apple!! [short edit]", "Bad Apple!!"),
+                ("Short Hair", "Short Hair"),
+            ],
+        );
+    }
+
+    #[test]
+    fn strip_sped_up_ver() {
+        assert_preset(
+            RedirectPreset::SpedUpVer,
+            &[
+                ("Padoru (Sped Up Ver.)", "Padoru"),
+                ("Snowman (sped up)", "Snowman"),
+                ("Kyu-kurarin [Sped-Up Version]", "Kyu-kurarin"),
+                ("Speed Up", "Speed Up"),
+            ],
+        );
+    }
+
+    #[test]
+    fn strip_nightcore_mix() {
+        assert_preset(
+            RedirectPreset::NightcoreMix,
+            &[
+                ("Angel With A Shotgun (Nightcore Mix)", "Angel With A Shotgun"),
+                ("Monster (Nightcore)", "Monster"),
+                ("Rockefeller Street [Nightcore Ver.]", "Rockefeller Street"),
+                ("Nightcore Is Not Dead", "Nightcore Is Not Dead"),
+            ],
+        );
+    }
+
+    #[test]
+    fn strip_extended() {
+        assert_preset(
+            RedirectPreset::Extended,
+            &[
+                ("Galaxy Collapse [Extended]", "Galaxy Collapse"),
+                ("Teo (Extended Mix)", "Teo"),
+                ("Through the Fire and Flames (Extended Ver.)", "Through the Fire and Flames"),
+                ("Extended Play", "Extended Play"),
+            ],
+        );
+    }
+}
@@ -1,18 +1,26 @@
-use crate::config::{Mode, ScrobblerConfig};
+use crate::{
+    config::{Mode, ScrobblerConfig, ScrobblerMusicBrainzConfig},
+    logger::Logger,
+    metrics::Metrics,
+    musicbrainz::{MusicBrainzClient, MusicBrainzRelease, RecordingMatch, ScoredRecording},
+};
 use anyhow::{Context, Result, bail};
 use colored::Colorize;
-use musicbrainz_rs::{
-    Search,
-    entity::{
-        recording::{Recording, RecordingSearchQuery},
-        release_group::ReleaseGroupPrimaryType,
-    },
+use musicbrainz_rs::entity::{
+    recording::{Recording, RecordingSearchQuery},
+    release::Release,
+    release_group::ReleaseGroupPrimaryType,
 };
 use reqwest::{StatusCode, blocking::Client};
 use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 pub struct Score {
+    pub id: u64,
+
+    #[serde(default)]
+    pub ruleset_id: u8,
+
     pub passed: bool,
     pub mods: Vec<ScoreMods>,
     pub started_at: Option<String>,
@@ -32,10 +40,15 @@ impl Score {
             request = request.query(&[("mode", &config.mode)]);
         }
 
-        let response = request.send().context("Could not send request to get user's recent score.")?;
+        let response = request
+            .send()
+            .inspect_err(|_| Metrics::record_osu_api_error(None))
+            .context("Could not send request to get user's recent score.")?;
         let status_code = response.status();
 
         if status_code != StatusCode::OK {
+            Metrics::record_osu_api_error(Some(status_code.as_u16()));
+
             let cause = if status_code == StatusCode::NOT_FOUND {
                 format!("Invalid user ID: {}", config.user_id.to_string().bright_blue())
             } else {
@@ -50,49 +63,99 @@ impl Score {
         if scores.is_empty() { Ok(None) } else { Ok(Some(scores.remove(0))) }
     }
 
-    pub fn get_album_name(&self, artist: &str, title: &str) -> Option<String> {
-        let mut title_album = None;
-        let mut title_ep = None;
-        let mut title_single = None;
-        let mut title_other = None;
-        let mut title_unrecognized = None;
-        let mut title_first = None;
-
-        for recording in self.get_musicbrainz_recordings(artist, title) {
-            let Some(releases) = recording.releases else { continue };
-
-            for release in releases {
-                if title_first.is_none() {
-                    title_first = Some(release.title.clone());
-                }
-
-                let release_group_primary_type = release
-                    .release_group
-                    .as_ref()
-                    .and_then(|release_group| release_group.primary_type.as_ref())
-                    .unwrap_or(&ReleaseGroupPrimaryType::UnrecognizedReleaseGroupPrimaryType);
-
-                let release_group_secondary_types_is_empty =
-                    release.release_group.as_ref().map(|release_group| release_group.secondary_types.is_empty()).unwrap_or_default();
-
-                let option = match release_group_primary_type {
-                    ReleaseGroupPrimaryType::Album => &mut title_album,
-                    ReleaseGroupPrimaryType::Ep => &mut title_ep,
-                    ReleaseGroupPrimaryType::Single => &mut title_single,
-                    ReleaseGroupPrimaryType::Other => &mut title_other,
-                    _ => &mut title_unrecognized,
-                };
-
-                if option.is_none() && release_group_secondary_types_is_empty {
-                    _ = option.insert(release.title);
-                }
+    pub fn get_mode(&self) -> Mode {
+        match self.ruleset_id {
+            1 => Mode::Taiko,
+            2 => Mode::Fruits,
+            3 => Mode::Mania,
+            _ => Mode::Osu,
+        }
+    }
+
+    pub fn get_musicbrainz_release(
+        &self,
+        artist: &str,
+        title: &str,
+        config: &ScrobblerMusicBrainzConfig,
+    ) -> Result<Option<MusicBrainzRelease>> {
+        let recordings = self.get_musicbrainz_recordings(artist, title, config)?;
+        let ranked_matches = RecordingMatch::get_ranked(&recordings, self, artist);
+
+        if let Some(best_match) = ranked_matches.first()
+            && best_match.confidence < config.min_confidence
+        {
+            Logger::warn(
+                "MusicBrainz",
+                format!(
+                    "Rejected recording {} ({best_match}) as it is below the minimum confidence ({}).",
+                    Self::get_recording_text(best_match),
+                    config.min_confidence.to_string().bright_blue(),
+                ),
+                true,
+            );
+
+            return Ok(None);
+        }
+
+        // Candidates without any release can't provide an album name, so the next one above the minimum confidence is tried instead
+        for recording_match in ranked_matches.iter().take_while(|recording_match| recording_match.confidence >= config.min_confidence) {
+            let recording_text = Self::get_recording_text(recording_match);
+
+            let Some(release) = Self::get_preferred_release(recording_match.recording) else {
+                Logger::warn("MusicBrainz", format!("Skipped recording {recording_text} ({recording_match}) as it has no releases."), true);
+                continue;
+            };
+
+            Logger::success("MusicBrainz", format!("Matched recording {recording_text} ({recording_match})."), true);
+            return Ok(Some(MusicBrainzRelease::new(release, recording_match.recording)));
+        }
+
+        Ok(None)
+    }
+
+    fn get_recording_text(recording_match: &RecordingMatch) -> String {
+        format!("{} by {}", recording_match.recording.title.bright_blue(), recording_match.get_artist_credit().bright_blue())
+    }
+
+    fn get_preferred_release(recording: &Recording) -> Option<&Release> {
+        let mut release_album = None;
+        let mut release_ep = None;
+        let mut release_single = None;
+        let mut release_other = None;
+        let mut release_unrecognized = None;
+        let mut release_first = None;
+
+        for release in recording.releases.iter().flatten() {
+            if release_first.is_none() {
+                release_first = Some(release);
+            }
+
+            let release_group_primary_type = release
+                .release_group
+                .as_ref()
+                .and_then(|release_group| release_group.primary_type.as_ref())
+                .unwrap_or(&ReleaseGroupPrimaryType::UnrecognizedReleaseGroupPrimaryType);
+
+            let release_group_secondary_types_is_empty =
+                release.release_group.as_ref().map(|release_group| release_group.secondary_types.is_empty()).unwrap_or_default();
+
+            let option = match release_group_primary_type {
+                ReleaseGroupPrimaryType::Album => &mut release_album,
+                ReleaseGroupPrimaryType::Ep => &mut release_ep,
+                ReleaseGroupPrimaryType::Single => &mut release_single,
+                ReleaseGroupPrimaryType::Other => &mut release_other,
+                _ => &mut release_unrecognized,
+            };
+
+            if option.is_none() && release_group_secondary_types_is_empty {
+                _ = option.insert(release);
             }
         }
 
-        title_album.or(title_ep).or(title_single).or(title_other).or(title_unrecognized).or(title_first)
+        release_album.or(release_ep).or(release_single).or(release_other).or(release_unrecognized).or(release_first)
     }
 
-    fn get_musicbrainz_recordings(&self, artist: &str, title: &str) -> Vec<Recording> {
+    fn get_musicbrainz_recordings(&self, artist: &str, title: &str, config: &ScrobblerMusicBrainzConfig) -> Result<Vec<ScoredRecording>> {
         let artist = artist.to_string();
         let title = title.to_string();
 
@@ -131,9 +194,7 @@ impl Score {
             .alias(&self.beatmapset.title_unicode);
 
         let query = RecordingSearchQuery::query_builder().expr(&mut query_artist).and().expr(&mut query_title).build();
-        let results = Recording::search(query).limit(100).execute();
-
-        results.map(|results| results.entities).unwrap_or_default()
+        MusicBrainzClient::new(config).search_recordings(&query)
     }
 }
 
@@ -157,10 +218,17 @@ pub struct Beatmap {
 
 #[derive(Deserialize, Debug)]
 pub struct Beatmapset {
+    pub id: u32,
     pub artist: String,
     pub artist_unicode: String,
     pub title: String,
     pub title_unicode: String,
+
+    #[serde(default)]
+    pub creator: String,
+
+    #[serde(default)]
+    pub source: String,
 }
 
 #[derive(Deserialize, Debug)]
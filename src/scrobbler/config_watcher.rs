@@ -0,0 +1,46 @@
+use crate::scrobbler::ScrobblerAction;
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher, recommended_watcher};
+use std::{
+    path::PathBuf,
+    sync::mpsc::{Sender, channel},
+    thread::spawn,
+    time::Duration,
+};
+
+// Editors often save in several steps, so changes are only reported once the file has been quiet for this long
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
+
+pub fn watch(config_path: PathBuf, sender: Sender<ScrobblerAction>) -> Result<()> {
+    let config_dir = config_path.parent().context("Could not resolve the directory of config file.")?.to_path_buf();
+    let config_file_name = config_path.file_name().map(|file_name| file_name.to_os_string());
+    let (event_sender, event_receiver) = channel();
+
+    // The directory is watched instead of the file itself so that atomic saves, which replace the file, are picked up
+    let mut watcher = recommended_watcher(move |event: notify::Result<Event>| {
+        let Ok(event) = event else { return };
+
+        if !matches!(event.kind, EventKind::Access(_))
+            && event.paths.iter().any(|path| path.file_name().map(|file_name| file_name.to_os_string()) == config_file_name)
+        {
+            _ = event_sender.send(());
+        }
+    })
+    .context("Could not create config file watcher.")?;
+
+    watcher.watch(&config_dir, RecursiveMode::NonRecursive).context("Could not watch config file.")?;
+
+    spawn(move || {
+        let _watcher = watcher;
+
+        while event_receiver.recv().is_ok() {
+            while event_receiver.recv_timeout(DEBOUNCE_DURATION).is_ok() {}
+
+            if sender.send(ScrobblerAction::Reload).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
@@ -1,77 +1,358 @@
 mod queries;
+mod responses;
 
-use crate::{config::LastfmConfig, logger::Logger, scrobbler::REQWEST};
-use anyhow::{Context, Result, bail};
-use chrono::Utc;
+use crate::{
+    config::{Config, LastfmConfig},
+    logger::Logger,
+    scrobbler::{ConnectionError, REQWEST, Track, clone_error},
+};
+use anyhow::{Context, Error, Result, anyhow, bail};
 use colored::Colorize;
+use md5::compute;
 use queries::LastfmQuery;
-use reqwest::StatusCode;
-use serde::Deserialize;
+use responses::{LastfmError, LastfmIgnoredScrobble, LastfmScrobbleResponse, LastfmSession, LastfmToken};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, from_str, from_value, to_string_pretty};
+use std::{
+    collections::BTreeMap,
+    fs::{OpenOptions, read_to_string},
+    io::{Write, stdin},
+    time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{
+    fs::Permissions,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+};
 
 const API_BASE_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+const AUTH_URL: &str = "https://www.last.fm/api/auth/";
+const SESSION_FILE_NAME: &str = "last_fm_session.json";
+// Last.fm accepts up to 50 scrobbles per request
+const MAX_TRACKS_PER_REQUEST: usize = 50;
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(300);
 
 #[derive(Debug)]
 pub struct LastfmScrobbler {
     config: LastfmConfig,
     session_key: String,
+    // Set after a rate limit error, which is returned again without sending anything until the back-off is over
+    rate_limit: Option<(Instant, LastfmError)>,
 }
 
-#[derive(Deserialize)]
-struct LastfmSession {
-    session: LastfmSessionData,
-}
-
-#[derive(Deserialize)]
-struct LastfmSessionData {
+// The session is tied to the API key it was issued for and the account that approved access
+// Only a hash of the API key is stored, so a key referenced from elsewhere does not end up in plain text
+#[derive(Deserialize, Serialize)]
+struct LastfmStoredSession {
     key: String,
     name: String,
+    #[serde(default)]
+    api_key_hash: String,
 }
 
 impl LastfmScrobbler {
     pub fn new(config: LastfmConfig) -> Result<Self> {
-        let response = REQWEST
-            .post(API_BASE_URL)
-            .header("content-length", "0")
-            .query(
-                &LastfmQuery::new()
-                    .insert("api_key", &config.api_key)
-                    .insert("method", "auth.getMobileSession")
-                    .insert("username", &config.username)
-                    .insert("password", &config.password)
-                    .sign(&config.api_secret),
-            )
-            .send()
-            .and_then(|response| response.json::<LastfmSession>());
-
-        let session = response.context("Invalid Last.fm credentials provided.")?;
-        Logger::success("Last.fm", format!("Successfully authenticated with username {}.", session.session.name.bright_blue()), false);
-
-        Ok(Self { config, session_key: session.session.key })
+        if let Some(session_key) = config.session_key.as_ref() {
+            let session_key = session_key.to_string();
+            Logger::success("Last.fm", format!("Using session key for username {}.", config.username.bright_blue()), false);
+            return Ok(Self { config, session_key, rate_limit: None });
+        }
+
+        let Some(session) = Self::load_session(&config)? else {
+            bail!(
+                "No valid Last.fm session found for username {}. Please run {} first.",
+                config.username.bright_blue(),
+                "osu-scrobbler auth lastfm".bright_blue(),
+            );
+        };
+
+        Logger::success("Last.fm", format!("Using stored session for username {}.", session.name.bright_blue()), false);
+
+        Ok(Self { config, session_key: session.key, rate_limit: None })
+    }
+
+    pub fn get_config(&self) -> &LastfmConfig {
+        &self.config
+    }
+
+    pub fn run_auth_command() -> Result<()> {
+        let (config, _, _) = Config::init()?;
+        let config = config.last_fm.context("Please provide configuration for Last.fm.")?;
+
+        if config.session_key.is_some() {
+            Logger::warn(
+                "Last.fm",
+                "A session key is set in config and takes precedence over the stored session. Remove it to use the new session.",
+                false,
+            );
+        }
+
+        Self::authenticate(&config)
+    }
+
+    fn load_session(config: &LastfmConfig) -> Result<Option<LastfmStoredSession>> {
+        let path = Config::get_data_path(SESSION_FILE_NAME)?;
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let session_string = read_to_string(&path).context("An error occurred while trying to read Last.fm session file.")?;
+        let session = from_str::<LastfmStoredSession>(&session_string).context("An error occurred while parsing Last.fm session file.")?;
+
+        if session.api_key_hash != Self::get_api_key_hash(config) || !session.name.eq_ignore_ascii_case(&config.username) {
+            Logger::warn(
+                "Last.fm",
+                format!("Ignoring stored session for username {} as it does not match the configured account.", session.name.bright_blue()),
+                false,
+            );
+
+            return Ok(None);
+        }
+
+        Ok(Some(session))
     }
 
-    pub fn scrobble(&self, artist: &str, title: &str, album: Option<&str>, total_length: u32) -> Result<()> {
-        let status = REQWEST
-            .post(API_BASE_URL)
-            .header("content-length", "0")
-            .query(
-                &LastfmQuery::new()
-                    .insert("api_key", &self.config.api_key)
-                    .insert("sk", &self.session_key)
-                    .insert("method", "track.scrobble")
-                    .insert("artist[0]", artist)
-                    .insert("track[0]", title)
-                    .insert("album[0]", album.unwrap_or_default())
-                    .insert("duration[0]", total_length)
-                    .insert("timestamp[0]", Utc::now().timestamp())
-                    .sign(&self.config.api_secret),
-            )
-            .send()?
-            .status();
-
-        if status != StatusCode::OK {
-            bail!("Received status code {status}.");
+    // Runs the desktop auth flow, which requires the user to approve access in the browser
+    fn authenticate(config: &LastfmConfig) -> Result<()> {
+        let token = Self::request::<LastfmToken>(
+            LastfmQuery::new().insert("api_key", &config.api_key).insert("method", "auth.getToken").sign(&config.api_secret),
+        )
+        .context("Could not get Last.fm auth token.")?
+        .token;
+
+        let auth_url = format!("{AUTH_URL}?api_key={}&token={token}", config.api_key);
+        Logger::warn("Last.fm", format!("Please approve access at {} and press enter to continue.", auth_url.bright_blue()), false);
+        stdin().read_line(&mut String::new())?;
+
+        let session = Self::request::<LastfmSession>(
+            LastfmQuery::new()
+                .insert("api_key", &config.api_key)
+                .insert("method", "auth.getSession")
+                .insert("token", &token)
+                .sign(&config.api_secret),
+        )
+        .context("Could not get Last.fm session. Please make sure access has been approved.")?
+        .session;
+
+        if !session.name.eq_ignore_ascii_case(&config.username) {
+            bail!(
+                "Access was approved for username {}, but the config is set to username {}.",
+                session.name.bright_blue(),
+                config.username.bright_blue(),
+            );
         }
 
+        let stored_session = LastfmStoredSession { key: session.key, name: session.name, api_key_hash: Self::get_api_key_hash(config) };
+        Self::write_session(&to_string_pretty(&stored_session)?)
+            .context("An error occurred while trying to write Last.fm session file.")?;
+
+        Logger::success("Last.fm", format!("Successfully authenticated with username {}.", stored_session.name.bright_blue()), false);
+
+        Ok(())
+    }
+
+    fn get_api_key_hash(config: &LastfmConfig) -> String {
+        format!("{:x}", compute(config.api_key.to_string()))
+    }
+
+    // The session key grants access to the account, so the file is only readable by its owner
+    fn write_session(session_string: &str) -> Result<()> {
+        let path = Config::get_data_path(SESSION_FILE_NAME)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&path)?;
+
+        #[cfg(unix)]
+        file.set_permissions(Permissions::from_mode(0o600))?;
+
+        file.write_all(session_string.as_bytes())?;
+
         Ok(())
     }
+
+    fn request<T: DeserializeOwned>(query: BTreeMap<String, String>) -> Result<T> {
+        // Parameters go in the body, since a batch of scrobbles can exceed URL length limits
+        let response = REQWEST.post(API_BASE_URL).form(&query).send().context("Could not send request to Last.fm.")?;
+
+        let status = response.status();
+        let body = response.json::<Value>().with_context(|| format!("Received status code {status} with an invalid body."))?;
+
+        if let Ok(error) = from_value::<LastfmError>(body.clone()) {
+            return Err(error.into());
+        }
+
+        from_value(body).context("Could not deserialize Last.fm response.")
+    }
+
+    // Returns one result per track, in the same order
+    pub fn scrobble(&mut self, tracks: &[Track]) -> Vec<Result<()>> {
+        if let Some((_, error)) = self.rate_limit.as_ref().filter(|(backoff_until, _)| *backoff_until > Instant::now()) {
+            return tracks.iter().map(|_| Err(error.clone().into())).collect();
+        }
+
+        let mut results = Vec::with_capacity(tracks.len());
+        let mut retryable_error = None;
+
+        for chunk in tracks.chunks(MAX_TRACKS_PER_REQUEST) {
+            // The remaining chunks are bound to fail the same way, so they are not sent
+            if let Some(error) = retryable_error.as_ref() {
+                results.extend(chunk.iter().map(|_| Err(clone_error::<LastfmError>(error))));
+                continue;
+            }
+
+            match self.submit_chunk(chunk) {
+                Ok(chunk_results) => results.extend(chunk_results),
+                Err(error) => {
+                    results.extend(chunk.iter().map(|_| Err(clone_error::<LastfmError>(&error))));
+
+                    if let Some(lastfm_error) = error.downcast_ref::<LastfmError>().filter(|error| error.is_rate_limited()) {
+                        Logger::warn(
+                            "Last.fm",
+                            format!(
+                                "Rate limit exceeded. Holding off for {} seconds.",
+                                RATE_LIMIT_BACKOFF.as_secs().to_string().bright_blue()
+                            ),
+                            true,
+                        );
+                        self.rate_limit = Some((Instant::now() + RATE_LIMIT_BACKOFF, lastfm_error.clone()));
+                    }
+
+                    if Self::is_retryable(&error) {
+                        retryable_error = Some(error);
+                    }
+                },
+            }
+        }
+
+        results
+    }
+
+    // An invalid session is kept for a later retry too, since the scrobbles go through once the user authorizes again
+    pub fn is_retryable(error: &Error) -> bool {
+        let is_lastfm_error_retryable = error
+            .downcast_ref::<LastfmError>()
+            .is_some_and(|error| error.is_unavailable() || error.is_rate_limited() || error.error == LastfmError::INVALID_SESSION_KEY);
+
+        is_lastfm_error_retryable
+            || error.downcast_ref::<LastfmIgnoredScrobble>().is_some_and(LastfmIgnoredScrobble::is_retryable)
+            || error.is::<ConnectionError>()
+    }
+
+    // Retryable errors are returned as is rather than retried here, so the caller can decide when to try again without blocking
+    fn submit_chunk(&mut self, tracks: &[Track]) -> Result<Vec<Result<()>>> {
+        let error = match self.submit(tracks) {
+            Ok(results) => return Ok(results),
+            Err(error) => error,
+        };
+
+        if error.downcast_ref::<LastfmError>().is_none_or(|error| error.error != LastfmError::INVALID_SESSION_KEY) {
+            return Err(error);
+        }
+
+        if self.config.session_key.is_some() {
+            return Err(error
+                .context("Session key in config is no longer valid. Please update it, or remove it and run osu-scrobbler auth lastfm."));
+        }
+
+        // The session might have been renewed with the auth command while the scrobbler is running
+        let new_session = Self::load_session(&self.config).ok().flatten().filter(|session| session.key != self.session_key);
+
+        let Some(new_session) = new_session else {
+            return Err(error.context("Stored session is no longer valid. Please run osu-scrobbler auth lastfm."));
+        };
+
+        Logger::success("Last.fm", format!("Using renewed stored session for username {}.", new_session.name.bright_blue()), true);
+        self.session_key = new_session.key;
+
+        self.submit(tracks)
+    }
+
+    fn submit(&self, tracks: &[Track]) -> Result<Vec<Result<()>>> {
+        let mut query =
+            LastfmQuery::new().insert("api_key", &self.config.api_key).insert("sk", &self.session_key).insert("method", "track.scrobble");
+
+        for (index, track) in tracks.iter().enumerate() {
+            query = query
+                .insert(format!("artist[{index}]"), &track.artist)
+                .insert(format!("track[{index}]"), &track.title)
+                .insert(format!("album[{index}]"), track.album.as_deref().unwrap_or_default())
+                .insert(format!("mbid[{index}]"), track.recording_mbid.as_deref().unwrap_or_default())
+                .insert(format!("duration[{index}]"), track.duration_secs)
+                .insert(format!("timestamp[{index}]"), track.listened_at);
+        }
+
+        let response = Self::request::<LastfmScrobbleResponse>(query.sign(&self.config.api_secret))?;
+
+        Ok(Self::get_results(tracks.len(), response))
+    }
+
+    // Scrobbles are returned in the same order as they were submitted
+    fn get_results(track_count: usize, response: LastfmScrobbleResponse) -> Vec<Result<()>> {
+        let mut scrobbles = response.scrobbles.scrobble.into_iter();
+
+        (0..track_count)
+            .map(|_| match scrobbles.next() {
+                Some(scrobble) => match scrobble.ignored_message.get_reason() {
+                    Some(reason) => Err(LastfmIgnoredScrobble { code: scrobble.ignored_message.code, reason }.into()),
+                    None => Ok(()),
+                },
+                None => Err(anyhow!("Last.fm did not return a result for this scrobble.")),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        LastfmScrobbler,
+        responses::{LastfmError, LastfmIgnoredScrobble},
+    };
+    use crate::scrobbler::ConnectionError;
+    use anyhow::{Error, anyhow};
+    use serde_json::from_str;
+
+    #[test]
+    fn map_results_to_tracks() {
+        let response = from_str(
+            r##"{"scrobbles":{"scrobble":[{"artist":{"corrected":"0","#text":"Camellia"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"Ghost"},"ignoredMessage":{"code":"0","#text":""},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1700000000"},{"artist":{"corrected":"0","#text":"xi"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"FREEDOM DiVE"},"ignoredMessage":{"code":"3","#text":"Timestamp too old"},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1000000000"},{"artist":{"corrected":"0","#text":"DragonForce"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"Through the Fire and Flames"},"ignoredMessage":{"code":"0","#text":""},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1700000300"}],"@attr":{"ignored":1,"accepted":2}}}"##,
+        )
+        .unwrap();
+
+        let results = LastfmScrobbler::get_results(4, response)
+            .into_iter()
+            .map(|result| result.map_err(|error| error.to_string()))
+            .collect::<Vec<Result<(), String>>>();
+
+        assert_eq!(
+            results,
+            [
+                Ok(()),
+                Err("Scrobble was ignored. Timestamp is too old (code 3): Timestamp too old.".into()),
+                Ok(()),
+                Err("Last.fm did not return a result for this scrobble.".into()),
+            ],
+        );
+    }
+
+    #[test]
+    fn classify_retryable_errors() {
+        let get_lastfm_error = |error| Error::new(LastfmError { error, message: "".into() });
+        let get_ignored_scrobble = |code| Error::new(LastfmIgnoredScrobble { code, reason: "".into() });
+
+        for error in [get_lastfm_error(9), get_lastfm_error(11), get_lastfm_error(16), get_lastfm_error(29), get_ignored_scrobble(5)] {
+            assert!(LastfmScrobbler::is_retryable(&error), "{error}");
+        }
+
+        assert!(LastfmScrobbler::is_retryable(&Error::new(ConnectionError("".into())).context("Could not send request to Last.fm.")));
+
+        for error in [get_lastfm_error(6), get_lastfm_error(13), get_ignored_scrobble(1), get_ignored_scrobble(3), anyhow!("Other error")] {
+            assert!(!LastfmScrobbler::is_retryable(&error), "{error}");
+        }
+    }
 }
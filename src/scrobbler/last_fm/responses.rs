@@ -0,0 +1,196 @@
+use serde::{Deserialize, Deserializer, de::Error as _};
+use serde_json::Value;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+#[derive(Deserialize)]
+pub struct LastfmToken {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmSession {
+    pub session: LastfmSessionData,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmSessionData {
+    pub key: String,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmScrobbleResponse {
+    pub scrobbles: LastfmScrobbles,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmScrobbles {
+    #[serde(deserialize_with = "deserialize_one_or_many")]
+    pub scrobble: Vec<LastfmScrobble>,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmScrobble {
+    #[serde(rename = "ignoredMessage")]
+    pub ignored_message: LastfmIgnoredMessage,
+}
+
+#[derive(Deserialize)]
+pub struct LastfmIgnoredMessage {
+    #[serde(deserialize_with = "deserialize_code")]
+    pub code: u32,
+
+    #[serde(rename = "#text", default)]
+    pub text: String,
+}
+
+impl LastfmIgnoredMessage {
+    pub const DAILY_LIMIT_EXCEEDED: u32 = 5;
+
+    pub fn get_reason(&self) -> Option<String> {
+        let reason = match self.code {
+            0 => return None,
+            1 => "Artist was ignored",
+            2 => "Track was ignored",
+            3 => "Timestamp is too old",
+            4 => "Timestamp is too new",
+            5 => "Daily scrobble limit was exceeded",
+            _ => "Unknown reason",
+        };
+
+        let text = self.text.trim_end_matches('.');
+
+        if text.is_empty() {
+            Some(format!("{reason} (code {})", self.code))
+        } else {
+            Some(format!("{reason} (code {}): {text}", self.code))
+        }
+    }
+}
+
+// A scrobble that was part of a successful request but was not recorded
+#[derive(Clone, Debug)]
+pub struct LastfmIgnoredScrobble {
+    pub code: u32,
+    pub reason: String,
+}
+
+impl LastfmIgnoredScrobble {
+    // The daily limit resets on its own, unlike the other reasons which would be ignored again
+    pub fn is_retryable(&self) -> bool {
+        self.code == LastfmIgnoredMessage::DAILY_LIMIT_EXCEEDED
+    }
+}
+
+impl Display for LastfmIgnoredScrobble {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Scrobble was ignored. {}.", self.reason)
+    }
+}
+
+impl Error for LastfmIgnoredScrobble {}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct LastfmError {
+    pub error: u32,
+    pub message: String,
+}
+
+impl LastfmError {
+    pub const INVALID_SESSION_KEY: u32 = 9;
+    pub const RATE_LIMIT_EXCEEDED: u32 = 29;
+
+    // Service offline and temporarily unavailable
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self.error, 11 | 16)
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        self.error == Self::RATE_LIMIT_EXCEEDED
+    }
+}
+
+impl Display for LastfmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Received error {}: {}", self.error, self.message)
+    }
+}
+
+impl Error for LastfmError {}
+
+// Last.fm returns a single object instead of an array when only one track was scrobbled
+fn deserialize_one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<LastfmScrobble>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(LastfmScrobble),
+        Many(Vec<LastfmScrobble>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(scrobble) => vec![scrobble],
+        OneOrMany::Many(scrobbles) => scrobbles,
+    })
+}
+
+// Numbers are sometimes returned as strings
+fn deserialize_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(number) => number.as_u64().map(|number| number as u32).ok_or_else(|| D::Error::custom("invalid code")),
+        Value::String(string) => string.parse().map_err(D::Error::custom),
+        _ => Err(D::Error::custom("expected a number or string")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LastfmIgnoredMessage, LastfmScrobbleResponse};
+    use serde_json::{from_str, from_value, json};
+
+    const SINGLE_RESPONSE: &str = r##"{"scrobbles":{"scrobble":{"artist":{"corrected":"0","#text":"Camellia"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"Ghost"},"ignoredMessage":{"code":"0","#text":""},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1700000000"},"@attr":{"ignored":0,"accepted":1}}}"##;
+
+    const MULTIPLE_RESPONSE: &str = r##"{"scrobbles":{"scrobble":[{"artist":{"corrected":"0","#text":"Camellia"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"Ghost"},"ignoredMessage":{"code":"0","#text":""},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1700000000"},{"artist":{"corrected":"0","#text":"xi"},"album":{"corrected":"0","#text":""},"track":{"corrected":"0","#text":"FREEDOM DiVE"},"ignoredMessage":{"code":"3","#text":"Timestamp too old"},"albumArtist":{"corrected":"0","#text":""},"timestamp":"1000000000"}],"@attr":{"ignored":1,"accepted":1}}}"##;
+
+    fn get_ignored_message(code: u32, text: &str) -> LastfmIgnoredMessage {
+        LastfmIgnoredMessage { code, text: text.into() }
+    }
+
+    #[test]
+    fn deserialize_single_scrobble() {
+        let response = from_str::<LastfmScrobbleResponse>(SINGLE_RESPONSE).unwrap();
+
+        assert_eq!(response.scrobbles.scrobble.len(), 1);
+        assert_eq!(response.scrobbles.scrobble[0].ignored_message.code, 0);
+    }
+
+    #[test]
+    fn deserialize_multiple_scrobbles() {
+        let response = from_str::<LastfmScrobbleResponse>(MULTIPLE_RESPONSE).unwrap();
+        let codes = response.scrobbles.scrobble.iter().map(|scrobble| scrobble.ignored_message.code).collect::<Vec<u32>>();
+
+        assert_eq!(codes, [0, 3]);
+        assert_eq!(response.scrobbles.scrobble[1].ignored_message.text, "Timestamp too old");
+    }
+
+    #[test]
+    fn deserialize_code() {
+        assert_eq!(from_value::<LastfmIgnoredMessage>(json!({ "code": 2, "#text": "" })).unwrap().code, 2);
+        assert_eq!(from_value::<LastfmIgnoredMessage>(json!({ "code": "4" })).unwrap().code, 4);
+        assert!(from_value::<LastfmIgnoredMessage>(json!({ "code": "unknown" })).is_err());
+        assert!(from_value::<LastfmIgnoredMessage>(json!({ "code": null })).is_err());
+    }
+
+    #[test]
+    fn get_reason() {
+        assert_eq!(get_ignored_message(0, "").get_reason(), None);
+        assert_eq!(
+            get_ignored_message(1, "Artist was ignored.").get_reason().as_deref(),
+            Some("Artist was ignored (code 1): Artist was ignored")
+        );
+        assert_eq!(get_ignored_message(5, "").get_reason().as_deref(), Some("Daily scrobble limit was exceeded (code 5)"));
+        assert_eq!(get_ignored_message(42, "Something else").get_reason().as_deref(), Some("Unknown reason (code 42): Something else"));
+    }
+}
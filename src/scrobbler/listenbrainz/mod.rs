@@ -1,22 +1,32 @@
 mod payloads;
+mod responses;
 
-use crate::{config::ListenBrainzConfig, logger::Logger, scrobbler::REQWEST};
-use anyhow::{Context, Result, bail};
+use crate::{
+    config::ListenBrainzConfig,
+    logger::Logger,
+    scrobbler::{ConnectionError, REQWEST, Track, clone_error},
+};
+use anyhow::{Context, Error, Result};
 use colored::Colorize;
 use payloads::{Listen, ListenType, Listens};
-use reqwest::StatusCode;
-use serde::Deserialize;
+use reqwest::{StatusCode, header::HeaderMap};
+use responses::{ListenBrainzError, ListenBrainzErrorBody, ListenBrainzToken};
+use std::time::{Duration, Instant};
 
 const API_BASE_URL: &str = "https://api.listenbrainz.org/1";
+const MAX_LISTENS_PER_REQUEST: usize = 1000;
+const FALLBACK_RATE_LIMIT_DELAY: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 pub struct ListenBrainzScrobbler {
     config: ListenBrainzConfig,
+    rate_limit: Option<RateLimit>,
 }
 
-#[derive(Deserialize)]
-struct ListenBrainzToken {
-    user_name: String,
+#[derive(Debug)]
+struct RateLimit {
+    remaining: u64,
+    reset_at: Instant,
 }
 
 impl ListenBrainzScrobbler {
@@ -32,22 +42,79 @@ impl ListenBrainzScrobbler {
         let token = response.context("Invalid ListenBrainz user token provided.")?;
         Logger::success("ListenBrainz", format!("Successfully authenticated with username {}.", token.user_name.bright_blue()), false);
 
-        Ok(Self { config })
+        Ok(Self { config, rate_limit: None })
+    }
+
+    pub fn get_config(&self) -> &ListenBrainzConfig {
+        &self.config
+    }
+
+    // Returns one result per track, in the same order
+    pub fn scrobble(&mut self, tracks: &[Track]) -> Vec<Result<()>> {
+        let mut results = Vec::with_capacity(tracks.len());
+
+        for chunk in tracks.chunks(MAX_LISTENS_PER_REQUEST) {
+            match self.submit(chunk) {
+                Ok(_) => results.extend(chunk.iter().map(|_| Ok(()))),
+                Err(error) => results.extend(chunk.iter().map(|_| Err(clone_error::<ListenBrainzError>(&error)))),
+            }
+        }
+
+        results
+    }
+
+    pub fn is_transient(error: &Error) -> bool {
+        match error.downcast_ref::<ListenBrainzError>() {
+            Some(error) => error.is_transient(),
+            None => error.is::<reqwest::Error>() || error.is::<ConnectionError>(),
+        }
+    }
+
+    fn update_rate_limit(&mut self, headers: &HeaderMap) {
+        let get_header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).and_then(|value| value.parse::<u64>().ok());
+
+        if let (Some(remaining), Some(reset_in_secs)) = (get_header("x-ratelimit-remaining"), get_header("x-ratelimit-reset-in")) {
+            self.rate_limit = Some(RateLimit { remaining, reset_at: Instant::now() + Duration::from_secs(reset_in_secs) });
+        }
     }
 
-    pub fn scrobble(&self, artist: &str, title: &str, album: Option<&str>, total_length: u32) -> Result<()> {
-        let user_token = &self.config.user_token;
-        let status = REQWEST
+    // Nothing is sent while the rate limit is exhausted, and the error is transient so the caller can retry once it resets
+    fn submit(&mut self, tracks: &[Track]) -> Result<()> {
+        let now = Instant::now();
+
+        if let Some(rate_limit) = self.rate_limit.as_ref().filter(|rate_limit| rate_limit.remaining == 0 && rate_limit.reset_at > now) {
+            let reset_in_secs = (rate_limit.reset_at - now).as_secs_f64().ceil();
+            let message = format!("Rate limit reached. Resets in {reset_in_secs} seconds.");
+            return Err(ListenBrainzError { status: StatusCode::TOO_MANY_REQUESTS, message }.into());
+        }
+
+        let authorization = format!("Token {}", self.config.user_token);
+        let listen_type = if tracks.len() == 1 { ListenType::Single } else { ListenType::Import };
+        let listens = Listens::new(listen_type, tracks.iter().map(Listen::new).collect());
+
+        let response = REQWEST
             .post(format!("{API_BASE_URL}/submit-listens"))
-            .header("authorization", format!("Token {user_token}"))
-            .json(&Listens::new(ListenType::Single, vec![Listen::new(artist, title, album, total_length)]))
-            .send()?
-            .status();
+            .header("authorization", authorization)
+            .json(&listens)
+            .send()
+            .context("Could not send request to ListenBrainz.")?;
+
+        self.update_rate_limit(response.headers());
+
+        let status = response.status();
+
+        if status == StatusCode::OK {
+            return Ok(());
+        }
 
-        if status != StatusCode::OK {
-            bail!("Received status code {status}.");
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            // Falls back to a short delay if the response did not include the rate limit headers
+            let reset_at =
+                self.rate_limit.as_ref().map_or_else(|| Instant::now() + FALLBACK_RATE_LIMIT_DELAY, |rate_limit| rate_limit.reset_at);
+            self.rate_limit = Some(RateLimit { remaining: 0, reset_at });
         }
 
-        Ok(())
+        let message = response.json::<ListenBrainzErrorBody>().map(|body| body.error).unwrap_or_else(|_| "No error message.".into());
+        Err(ListenBrainzError { status, message }.into())
     }
 }
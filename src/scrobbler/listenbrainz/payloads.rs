@@ -1,4 +1,4 @@
-use chrono::Utc;
+use crate::scrobbler::Track;
 use serde::Serialize;
 use std::fmt::Display;
 
@@ -12,6 +12,7 @@ pub struct Listens {
 #[serde(rename_all = "lowercase")]
 pub enum ListenType {
     Single,
+    Import,
 }
 
 impl Listens {
@@ -27,19 +28,21 @@ pub struct Listen {
 }
 
 impl Listen {
-    pub fn new<T: Display, U: Display, V: Display>(artist_name: T, track_name: U, release_name: Option<V>, duration: u32) -> Self {
+    pub fn new(track: &Track) -> Self {
         Self {
-            listened_at: Utc::now().timestamp(),
+            listened_at: track.listened_at,
             track_metadata: TrackMetadata::new(
-                artist_name,
-                track_name,
-                release_name,
+                &track.artist,
+                &track.title,
+                track.album.as_ref(),
                 TrackAdditionalInfo::new(
                     "osu!",
                     "osu-scrobbler (github.com/flazepe/osu-scrobbler)",
                     env!("CARGO_PKG_VERSION"),
-                    duration * 1000,
-                ),
+                    track.duration_secs * 1000,
+                )
+                .musicbrainz_ids(track.recording_mbid.as_ref(), track.release_mbid.as_ref(), &track.artist_mbids)
+                .artist_names(&track.artist_names),
             ),
         }
     }
@@ -78,6 +81,18 @@ pub struct TrackAdditionalInfo {
     pub submission_client: String,
     pub submission_client_version: String,
     pub duration_ms: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_mbid: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_mbid: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artist_mbids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artist_names: Vec<String>,
 }
 
 impl TrackAdditionalInfo {
@@ -92,6 +107,27 @@ impl TrackAdditionalInfo {
             submission_client: submission_client.to_string(),
             submission_client_version: submission_client_version.to_string(),
             duration_ms,
+            recording_mbid: None,
+            release_mbid: None,
+            artist_mbids: vec![],
+            artist_names: vec![],
         }
     }
+
+    pub fn musicbrainz_ids<T: Display, U: Display>(
+        mut self,
+        recording_mbid: Option<T>,
+        release_mbid: Option<U>,
+        artist_mbids: &[String],
+    ) -> Self {
+        self.recording_mbid = recording_mbid.map(|recording_mbid| recording_mbid.to_string());
+        self.release_mbid = release_mbid.map(|release_mbid| release_mbid.to_string());
+        self.artist_mbids = artist_mbids.to_vec();
+        self
+    }
+
+    pub fn artist_names(mut self, artist_names: &[String]) -> Self {
+        self.artist_names = artist_names.to_vec();
+        self
+    }
 }
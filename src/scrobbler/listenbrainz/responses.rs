@@ -0,0 +1,40 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+
+#[derive(Deserialize)]
+pub struct ListenBrainzToken {
+    pub user_name: String,
+}
+
+#[derive(Deserialize)]
+pub struct ListenBrainzErrorBody {
+    pub error: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListenBrainzError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ListenBrainzError {
+    // Rate limits and server errors are expected to resolve on their own, unlike an invalid token or a bad request
+    pub fn is_transient(&self) -> bool {
+        self.status == StatusCode::TOO_MANY_REQUESTS || self.status.is_server_error()
+    }
+}
+
+impl Display for ListenBrainzError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.status {
+            StatusCode::UNAUTHORIZED => write!(f, "Invalid ListenBrainz user token: {}", self.message),
+            status => write!(f, "Received status code {status}: {}", self.message),
+        }
+    }
+}
+
+impl Error for ListenBrainzError {}
@@ -1,52 +1,165 @@
+mod config_watcher;
 mod last_fm;
 mod listenbrainz;
+mod pause;
+mod queue;
+mod scrobble_log;
+#[cfg(unix)]
+mod signals;
+
+pub use last_fm::LastfmScrobbler;
 
 use crate::{
+    api::Api,
     config::{Config, ScrobblerConfig},
     logger::Logger,
+    metrics::Metrics,
+    musicbrainz::MusicBrainzCache,
     scores::Score,
-    scrobbler::{last_fm::LastfmScrobbler, listenbrainz::ListenBrainzScrobbler},
-    utils::{get_osu_pid, handle_redirects, validate_scrobble},
+    scrobbler::{listenbrainz::ListenBrainzScrobbler, pause::PauseState, queue::ScrobbleQueue, scrobble_log::ScrobbleLog},
+    utils::{get_album, get_metadata, get_osu_pid, handle_album_redirects, handle_featured_artists, handle_redirects, validate_scrobble},
 };
-use anyhow::{Context, Result};
-use chrono::DateTime;
+use anyhow::{Context, Error, Result, anyhow};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
+use md5::Digest;
 use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, to_value};
 use std::{
-    sync::LazyLock,
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    path::PathBuf,
+    slice,
+    sync::{
+        LazyLock,
+        mpsc::{Receiver, RecvTimeoutError, Sender, channel},
+    },
     thread::sleep,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant},
 };
 
 static REQWEST: LazyLock<Client> = LazyLock::new(Client::new);
 
+pub enum ScrobblerAction {
+    Pause(Option<Duration>),
+    Resume,
+    Poll,
+    Reload,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Track {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub duration_secs: u32,
+    pub recording_mbid: Option<String>,
+    pub release_mbid: Option<String>,
+    pub artist_mbids: Vec<String>,
+    pub artist_names: Vec<String>,
+    pub listened_at: i64,
+}
+
+// Network failures, kept as their own type so they can still be told apart after being copied for every track of a request
+#[derive(Clone, Debug)]
+pub struct ConnectionError(String);
+
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl StdError for ConnectionError {}
+
+// A failed request fails every track in it, so the error is copied for each track while keeping the types the backends check
+fn clone_error<T: StdError + Clone + Send + Sync + 'static>(error: &Error) -> Error {
+    if let Some(typed_error) = error.downcast_ref::<T>() {
+        let contexts = error.chain().take_while(|cause| !cause.is::<T>()).map(|cause| cause.to_string()).collect::<Vec<String>>();
+        return contexts.into_iter().rev().fold(Error::new(typed_error.clone()), |error, context| error.context(context));
+    }
+
+    if error.is::<reqwest::Error>() || error.is::<ConnectionError>() {
+        ConnectionError(format!("{error:#}")).into()
+    } else {
+        anyhow!("{error:#}")
+    }
+}
+
 #[derive(Debug)]
 pub struct Scrobbler {
     config: ScrobblerConfig,
-    config_modified: SystemTime,
+    config_value: Value,
+    config_path: PathBuf,
+    config_hash: Digest,
+    config_watched: bool,
     config_reload_result: Result<()>,
     last_fm: Option<LastfmScrobbler>,
     listenbrainz: Option<ListenBrainzScrobbler>,
+    musicbrainz_cache: MusicBrainzCache,
+    scrobble_log: ScrobbleLog,
+    queue: ScrobbleQueue,
     recent_score: Option<Score>,
     cooldown_secs: u64,
+    pause_state: PauseState,
+    // Keeps the channel open even when nothing else holds a sender
+    _action_sender: Sender<ScrobblerAction>,
+    action_receiver: Receiver<ScrobblerAction>,
 }
 
 impl Scrobbler {
     pub fn new() -> Result<Self> {
+        let (config, config_path, config_hash) = Config::init()?;
+
         Logger::success("Scrobbler", format!("Starting... ({})", format!("v{}", env!("CARGO_PKG_VERSION")).bright_blue()), false);
+        Logger::success("Config", format!("Successfully loaded from {}.", config_path.to_string_lossy().bright_blue()), false);
+        Logger::debug("Config", format!("{config:#?}"), false);
+
+        let (action_sender, action_receiver) = channel();
+
+        if let Some(metrics) = config.metrics.as_ref() {
+            Metrics::serve(metrics)?;
+        }
+
+        if let Some(api) = config.api.as_ref() {
+            Api::serve(api, action_sender.clone())?;
+        }
+
+        #[cfg(unix)]
+        signals::listen(action_sender.clone())?;
+
+        let config_watched = match config_watcher::watch(config_path.clone(), action_sender.clone()) {
+            Ok(_) => true,
+            Err(error) => {
+                Logger::warn("Config", format!("{error:?}\nFalling back to checking for changes periodically."), false);
+                false
+            },
+        };
+
+        let config_value = to_value(&config)?;
+        Api::update_status(|status| status.config = config_value.clone());
 
-        let (config, config_modified) = Config::init()?;
         let last_fm = if let Some(c) = config.last_fm { Some(LastfmScrobbler::new(c)?) } else { None };
         let listenbrainz = if let Some(c) = config.listenbrainz { Some(ListenBrainzScrobbler::new(c)?) } else { None };
 
         Ok(Self {
             config: config.scrobbler,
-            config_modified,
+            config_value,
+            config_path,
+            config_hash,
+            config_watched,
             config_reload_result: Ok(()),
             last_fm,
             listenbrainz,
+            musicbrainz_cache: MusicBrainzCache::load()?,
+            scrobble_log: ScrobbleLog::default(),
+            queue: ScrobbleQueue::load()?,
             recent_score: None,
             cooldown_secs: 0,
+            pause_state: PauseState::default(),
+            _action_sender: action_sender,
+            action_receiver,
         })
     }
 
@@ -58,31 +171,64 @@ impl Scrobbler {
         loop {
             self.cooldown_secs = 0;
 
-            self.reload_config();
+            // A failed reload is retried on every tick since the watcher only reports further changes to the file
+            if !self.config_watched || self.config_reload_result.is_err() {
+                self.reload_config();
+            }
+
+            self.pause_state.update(&self.config);
 
-            if get_osu_pid().is_some() {
+            let osu_running = get_osu_pid().is_some();
+            Api::update_status(|status| status.osu_running = osu_running);
+
+            if osu_running {
                 self.poll();
             }
 
+            self.flush_queue();
+
             self.cooldown_secs += 5;
 
-            sleep(Duration::from_secs(self.cooldown_secs));
+            self.wait(Duration::from_secs(self.cooldown_secs));
         }
     }
 
-    fn reload_config(&mut self) {
-        match self.config.reload(&mut self.config_modified).context("Could not reload config file.") {
-            Ok(new_recent_score) => {
-                if let Some(new_recent_score) = new_recent_score {
-                    self.recent_score = Some(new_recent_score);
-                }
+    // Sleeps until the cooldown is over while handling actions sent in the meantime
+    fn wait(&mut self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+
+        while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+            match self.action_receiver.recv_timeout(timeout) {
+                Ok(action) => self.handle_action(action),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    sleep(timeout);
+                    break;
+                },
+            }
+        }
+    }
 
-                self.config_reload_result = Ok(());
+    fn handle_action(&mut self, action: ScrobblerAction) {
+        match action {
+            ScrobblerAction::Pause(duration) => self.pause_state.pause(duration, &self.config),
+            ScrobblerAction::Resume => self.pause_state.resume(&self.config),
+            ScrobblerAction::Poll => self.poll(),
+            ScrobblerAction::Reload => {
+                self.reload_config();
+                self.pause_state.update(&self.config);
             },
+        }
+    }
+
+    fn reload_config(&mut self) {
+        match self.try_reload_config().context("Could not reload config file.") {
+            Ok(_) => self.config_reload_result = Ok(()),
             Err(new_error) => {
                 let new_error_string = format!("{new_error:?}");
 
                 if self.config_reload_result.as_ref().err().is_none_or(|error| format!("{error:?}") != new_error_string) {
+                    Api::set_last_error("Config", new_error_string.clone());
                     Logger::error("Config", new_error_string, false);
                 }
 
@@ -91,7 +237,87 @@ impl Scrobbler {
         }
     }
 
+    fn try_reload_config(&mut self) -> Result<()> {
+        let Some((new_config, new_config_hash)) = Config::read_if_changed(&self.config_path, &self.config_hash)? else { return Ok(()) };
+        let new_config_value = to_value(&new_config)?;
+        let mut reloaded_keys = Config::get_changed_keys(&self.config_value, &new_config_value)?;
+
+        // Secrets are redacted from the config value, so backends are compared separately
+        let last_fm_changed = self.last_fm.as_ref().map(LastfmScrobbler::get_config) != new_config.last_fm.as_ref();
+        let listenbrainz_changed = self.listenbrainz.as_ref().map(ListenBrainzScrobbler::get_config) != new_config.listenbrainz.as_ref();
+
+        for (key, changed) in [("last_fm", last_fm_changed), ("listenbrainz", listenbrainz_changed)] {
+            if changed && !reloaded_keys.iter().any(|reloaded_key| reloaded_key == key) {
+                reloaded_keys.push(key.into());
+            }
+        }
+
+        if reloaded_keys.is_empty() {
+            self.config_hash = new_config_hash;
+            return Ok(());
+        }
+
+        let is_reloaded = |key: &str| reloaded_keys.iter().any(|reloaded_key| reloaded_key == key);
+
+        // Anything that can fail happens before the new config is applied, so a failure keeps the previous state intact
+        let new_recent_score =
+            if is_reloaded("user_id") || is_reloaded("scrobble_fails") { Score::get_user_recent(&new_config.scrobbler)? } else { None };
+        let new_last_fm = if last_fm_changed { Some(new_config.last_fm.map(LastfmScrobbler::new).transpose()?) } else { None };
+        let new_listenbrainz =
+            if listenbrainz_changed { Some(new_config.listenbrainz.map(ListenBrainzScrobbler::new).transpose()?) } else { None };
+
+        if is_reloaded("logger") {
+            Logger::configure(&new_config.logger)?;
+        }
+
+        if let Some(new_last_fm) = new_last_fm {
+            if new_last_fm.is_none() {
+                Logger::success("Last.fm", "Removed scrobbler.", false);
+            }
+
+            self.last_fm = new_last_fm;
+        }
+
+        if let Some(new_listenbrainz) = new_listenbrainz {
+            if new_listenbrainz.is_none() {
+                Logger::success("ListenBrainz", "Removed scrobbler.", false);
+            }
+
+            self.listenbrainz = new_listenbrainz;
+        }
+
+        for key in ["metrics", "api"] {
+            if is_reloaded(key) {
+                Logger::warn("Config", format!("Changes to {} take effect after a restart.", key.bright_blue()), false);
+            }
+        }
+
+        if let Some(new_recent_score) = new_recent_score {
+            self.recent_score = Some(new_recent_score);
+        }
+
+        self.config = new_config.scrobbler;
+        self.config_value = new_config_value.clone();
+        self.config_hash = new_config_hash;
+        Api::update_status(|status| status.config = new_config_value);
+
+        Logger::success(
+            "Config",
+            format!(
+                "Successfully reloaded {} from {}.",
+                reloaded_keys.iter().map(|key| key.bright_blue().to_string()).collect::<Vec<String>>().join(", "),
+                self.config_path.to_string_lossy().bright_blue(),
+            ),
+            false,
+        );
+
+        Ok(())
+    }
+
     fn poll(&mut self) {
+        Logger::trace("Scrobbler", "Polling user's recent scores...", false);
+        Metrics::record_poll();
+
         match Score::get_user_recent(&self.config) {
             Ok(score) => {
                 let Some(score) = score else { return };
@@ -99,17 +325,53 @@ impl Scrobbler {
                 self.recent_score = Some(score);
             },
             Err(error) => {
+                Api::set_last_error("Scrobbler", format!("{error:#}"));
                 Logger::error("Scrobbler", error, false);
                 self.cooldown_secs += 10;
             },
         }
     }
 
+    // Queued tracks are kept across config reloads, and are only submitted while their backend is configured
+    fn flush_queue(&mut self) {
+        if !self.queue.is_flush_due() {
+            return;
+        }
+
+        if let Some(last_fm) = self.last_fm.as_mut() {
+            self.queue.flush("last_fm", "Last.fm", |tracks| last_fm.scrobble(tracks), LastfmScrobbler::is_retryable);
+        }
+
+        if let Some(listenbrainz) = self.listenbrainz.as_mut() {
+            self.queue.flush("listenbrainz", "ListenBrainz", |tracks| listenbrainz.scrobble(tracks), ListenBrainzScrobbler::is_transient);
+        }
+    }
+
     fn scrobble(&mut self, score: &Score) {
         if self.recent_score.as_ref().is_some_and(|recent_score| recent_score.ended_at == score.ended_at) {
             return;
         }
 
+        let _score_scope = Logger::scope([("score_id", score.id.into())]);
+        Metrics::record_score_seen();
+        Api::set_last_score(score);
+
+        if self.pause_state.is_paused() {
+            Logger::warn(
+                "Scrobbler",
+                format!(
+                    "Scrobbling is paused. Skipping score {} - {} [{}].",
+                    score.beatmapset.artist.bright_blue(),
+                    score.beatmapset.title.bright_blue(),
+                    score.beatmap.version.bright_blue(),
+                ),
+                false,
+            );
+
+            Metrics::record_skip("paused");
+            return;
+        }
+
         if !score.passed {
             let started_at = score.started_at.as_ref().and_then(|started_at| DateTime::parse_from_rfc3339(started_at).ok());
             let ended_at = DateTime::parse_from_rfc3339(&score.ended_at).ok();
@@ -129,6 +391,7 @@ impl Scrobbler {
                 let is_valid_scrobble = delta >= hit_length / 2. || delta >= 60. * 4.;
 
                 if !is_valid_scrobble {
+                    Metrics::record_skip("fail_threshold");
                     return;
                 }
             }
@@ -137,13 +400,9 @@ impl Scrobbler {
         let (artist_romanized, artist_original) = (&score.beatmapset.artist, &score.beatmapset.artist_unicode);
         let (title_romanized, title_original) = (&score.beatmapset.title, &score.beatmapset.title_unicode);
 
-        let mut artist = artist_romanized;
-        let mut title = title_romanized;
-
-        if self.config.use_original_metadata {
-            artist = artist_original;
-            title = title_original;
-        }
+        let mut artist = get_metadata(artist_romanized, artist_original, self.config.get_artist_metadata(), &self.config.original_scripts);
+        let mut title = get_metadata(title_romanized, title_original, self.config.get_title_metadata(), &self.config.original_scripts);
+        let _metadata_scope = Logger::scope([("artist", artist.into()), ("title", title.into())]);
 
         if let Err(error) = validate_scrobble(score, &self.config) {
             Logger::warn(
@@ -174,12 +433,23 @@ impl Scrobbler {
             format!(" (redirected from {})", old_title.bright_blue())
         });
 
-        let album = if self.config.fetch_album_names { score.get_album_name(artist, title) } else { None };
+        let (mut album, release) = get_album(score, artist, title, &self.config, &mut self.musicbrainz_cache);
+        let new_album = handle_album_redirects(score, artist, title, album.as_deref(), &self.config);
+
+        let album_redirected_text = new_album.map(|new_album| {
+            let old_album = album.replace(new_album);
+            format!(" (redirected from {})", old_album.as_deref().unwrap_or("Unknown Album").bright_blue())
+        });
+
+        let (featured_artist, featured_title, artist_names) = handle_featured_artists(artist, title, &self.config);
+        let artist = featured_artist.as_deref().unwrap_or(artist);
+        let title = featured_title.as_deref().unwrap_or(title);
+        let _track_scope = Logger::scope([("artist", artist.into()), ("title", title.into())]);
 
         Logger::success(
             "Scrobbler",
             format!(
-                "New score by {} ({}) found: {}{} - {}{} ({})",
+                "New score by {} ({}) found: {}{} - {}{} ({}{})",
                 score.user.username.bright_blue(),
                 score.user.id.to_string().bright_blue(),
                 artist.bright_blue(),
@@ -187,25 +457,75 @@ impl Scrobbler {
                 title.bright_blue(),
                 title_redirected_text.as_deref().unwrap_or_default(),
                 album.as_deref().unwrap_or("Unknown Album").bright_blue(),
+                album_redirected_text.as_deref().unwrap_or_default(),
             ),
             false,
         );
 
-        if self.config.log_scrobbles {
-            Logger::file(format!("{} | {artist} - {title}", score.ended_at));
+        let track = Track {
+            artist: artist.to_string(),
+            title: title.to_string(),
+            album,
+            duration_secs: score.beatmap.total_length,
+            recording_mbid: release.as_ref().map(|release| release.recording_mbid.clone()),
+            // A redirected album no longer corresponds to the matched MusicBrainz release
+            release_mbid: release.as_ref().filter(|_| album_redirected_text.is_none()).map(|release| release.mbid.clone()),
+            artist_mbids: release.map(|release| release.artist_mbids).unwrap_or_default(),
+            artist_names,
+            listened_at: Utc::now().timestamp(),
+        };
+
+        if self.config.log_scrobbles
+            && let Err(error) = self.scrobble_log.write(&self.config.scrobble_log, score, &track)
+        {
+            Logger::error("Scrobbler", format!("Could not write to scrobble log: {error:?}"), true);
         }
 
-        if let Some(last_fm) = self.last_fm.as_ref() {
-            match last_fm.scrobble(artist, title, album.as_deref(), score.beatmap.total_length) {
-                Ok(_) => Logger::success("Last.fm", "Successfully scrobbled score.", true),
-                Err(error) => Logger::error("Last.fm", error, true),
+        if let Some(last_fm) = self.last_fm.as_mut() {
+            let _backend_scope = Logger::scope([("backend", "last_fm".into())]);
+
+            match last_fm.scrobble(slice::from_ref(&track)).remove(0) {
+                Ok(_) => {
+                    Metrics::record_scrobble("last_fm", true);
+                    Api::set_last_scrobble("last_fm", artist, title, None);
+                    Logger::success("Last.fm", "Successfully scrobbled score.", true);
+                },
+                Err(error) => {
+                    Metrics::record_scrobble("last_fm", false);
+                    Api::set_last_scrobble("last_fm", artist, title, Some(format!("{error:#}")));
+                    Api::set_last_error("Last.fm", format!("{error:#}"));
+
+                    if LastfmScrobbler::is_retryable(&error) {
+                        Logger::warn("Last.fm", format!("{error:#}. Queued for a later retry."), true);
+                        self.queue.push("last_fm", "Last.fm", track.clone());
+                    } else {
+                        Logger::error("Last.fm", error, true);
+                    }
+                },
             };
         }
 
-        if let Some(listenbrainz) = self.listenbrainz.as_ref() {
-            match listenbrainz.scrobble(artist, title, album.as_deref(), score.beatmap.total_length) {
-                Ok(_) => Logger::success("ListenBrainz", "Successfully scrobbled score.", true),
-                Err(error) => Logger::error("ListenBrainz", error, true),
+        if let Some(listenbrainz) = self.listenbrainz.as_mut() {
+            let _backend_scope = Logger::scope([("backend", "listenbrainz".into())]);
+
+            match listenbrainz.scrobble(slice::from_ref(&track)).remove(0) {
+                Ok(_) => {
+                    Metrics::record_scrobble("listenbrainz", true);
+                    Api::set_last_scrobble("listenbrainz", artist, title, None);
+                    Logger::success("ListenBrainz", "Successfully scrobbled score.", true);
+                },
+                Err(error) => {
+                    Metrics::record_scrobble("listenbrainz", false);
+                    Api::set_last_scrobble("listenbrainz", artist, title, Some(format!("{error:#}")));
+                    Api::set_last_error("ListenBrainz", format!("{error:#}"));
+
+                    if ListenBrainzScrobbler::is_transient(&error) {
+                        Logger::warn("ListenBrainz", format!("{error:#}. Queued for a later retry."), true);
+                        self.queue.push("listenbrainz", "ListenBrainz", track.clone());
+                    } else {
+                        Logger::error("ListenBrainz", error, true);
+                    }
+                },
             };
         }
     }
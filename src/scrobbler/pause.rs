@@ -0,0 +1,97 @@
+use crate::{
+    api::Api,
+    config::{Config, ScrobblerConfig},
+    logger::Logger,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use colored::Colorize;
+use std::{fs::read_to_string, time::Duration};
+
+#[derive(Debug, Default)]
+pub struct PauseState {
+    // Set through the API or a signal, where None as the end time means the pause lasts until resumed
+    manual_pause: Option<Option<DateTime<Utc>>>,
+    reason: Option<String>,
+}
+
+impl PauseState {
+    pub fn is_paused(&self) -> bool {
+        self.reason.is_some()
+    }
+
+    pub fn pause(&mut self, duration: Option<Duration>, config: &ScrobblerConfig) {
+        let until = duration.and_then(|duration| TimeDelta::from_std(duration).ok()).map(|duration| Utc::now() + duration);
+        self.manual_pause = Some(until);
+        self.update(config);
+    }
+
+    pub fn resume(&mut self, config: &ScrobblerConfig) {
+        self.manual_pause = None;
+        self.update(config);
+
+        if let Some(reason) = self.reason.as_ref() {
+            Logger::warn("Scrobbler", format!("Scrobbling is still paused {reason}."), false);
+        }
+    }
+
+    pub fn update(&mut self, config: &ScrobblerConfig) {
+        let reason = self.get_reason(config);
+
+        if reason.is_some() != self.reason.is_some() {
+            match reason.as_ref() {
+                Some(reason) => Logger::success("Scrobbler", format!("Paused scrobbling {reason}."), false),
+                None => Logger::success("Scrobbler", "Resumed scrobbling.", false),
+            }
+        }
+
+        let paused = reason.is_some();
+        Api::update_status(|status| status.paused = paused);
+
+        self.reason = reason;
+    }
+
+    fn get_reason(&mut self, config: &ScrobblerConfig) -> Option<String> {
+        let now = Utc::now();
+        let format_until = |until: DateTime<Utc>| format!(" until {}", until.to_rfc3339().bright_blue());
+
+        if let Some(until) = self.manual_pause {
+            if until.is_none_or(|until| now < until) {
+                return Some(format!("manually{}", until.map(format_until).unwrap_or_default()));
+            }
+
+            self.manual_pause = None;
+        }
+
+        if config.paused {
+            return Some("by the config".into());
+        }
+
+        if let Some(until) = config.paused_until.map(|until| until.to_utc())
+            && now < until
+        {
+            return Some(format!("by the config{}", format_until(until)));
+        }
+
+        let until = Self::get_pause_file_until(config)?;
+
+        if until.is_none_or(|until| now < until) {
+            return Some(format!("by the pause file{}", until.map(format_until).unwrap_or_default()));
+        }
+
+        None
+    }
+
+    // The pause file may contain a number of seconds counted from when it was written, or an RFC 3339 timestamp
+    fn get_pause_file_until(config: &ScrobblerConfig) -> Option<Option<DateTime<Utc>>> {
+        let path = Config::get_data_path(&config.pause_file).ok()?;
+        let modified = path.metadata().and_then(|metadata| metadata.modified()).ok()?;
+        let content = read_to_string(&path).unwrap_or_default();
+        let content = content.trim();
+
+        if let Ok(secs) = content.parse::<i64>() {
+            return Some(Some(DateTime::<Utc>::from(modified) + TimeDelta::seconds(secs)));
+        }
+
+        Some(DateTime::parse_from_rfc3339(content).ok().map(|until| until.to_utc()))
+    }
+}
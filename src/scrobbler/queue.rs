@@ -0,0 +1,220 @@
+use crate::{config::Config, logger::Logger, scrobbler::Track};
+use anyhow::{Context, Error, Result};
+use colored::Colorize;
+use serde_json::{from_str, to_string_pretty};
+use std::{
+    collections::BTreeMap,
+    fs::{read_to_string, write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+const QUEUE_FILE_NAME: &str = "scrobble_queue.json";
+// The oldest tracks are dropped beyond this, so an outage cannot grow the file without bounds
+const MAX_QUEUED_TRACKS: usize = 1000;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+// Tracks that failed to submit because of an error that is expected to resolve on its own, keyed by backend
+#[derive(Debug)]
+pub struct ScrobbleQueue {
+    path: PathBuf,
+    entries: BTreeMap<String, Vec<Track>>,
+    flushed_at: Option<Instant>,
+}
+
+impl ScrobbleQueue {
+    pub fn load() -> Result<Self> {
+        let path = Config::get_data_path(QUEUE_FILE_NAME)?;
+        let mut entries = BTreeMap::new();
+
+        if path.exists() {
+            let queue = read_to_string(&path)
+                .context("An error occurred while trying to read scrobble queue file.")
+                .and_then(|queue_string| from_str(&queue_string).context("An error occurred while parsing scrobble queue file."));
+
+            match queue {
+                Ok(queue) => entries = queue,
+                Err(error) => Logger::warn("Scrobbler", format!("{error:?}"), false),
+            }
+        }
+
+        Ok(Self { path, entries, flushed_at: None })
+    }
+
+    fn save(&self) {
+        let result = to_string_pretty(&self.entries).map_err(Error::from).and_then(|queue_string| {
+            write(&self.path, queue_string).context("An error occurred while trying to write scrobble queue file.")
+        });
+
+        if let Err(error) = result {
+            Logger::warn("Scrobbler", format!("{error:?}"), true);
+        }
+    }
+
+    pub fn push(&mut self, backend: &str, tag: &str, track: Track) {
+        let tracks = self.entries.entry(backend.into()).or_default();
+        tracks.push(track);
+
+        if tracks.len() > MAX_QUEUED_TRACKS {
+            let dropped_count = tracks.len() - MAX_QUEUED_TRACKS;
+            tracks.drain(..dropped_count);
+            Logger::warn(tag, format!("Queue is full. Dropped {} oldest queued scrobbles.", dropped_count.to_string().bright_blue()), true);
+        }
+
+        self.save();
+    }
+
+    // Submits the queued tracks of a backend in one go, keeping the ones that failed with a retryable error
+    pub fn flush(
+        &mut self,
+        backend: &str,
+        tag: &str,
+        scrobble: impl FnOnce(&[Track]) -> Vec<Result<()>>,
+        is_retryable: fn(&Error) -> bool,
+    ) {
+        let Some(tracks) = self.entries.remove(backend).filter(|tracks| !tracks.is_empty()) else { return };
+        let results = scrobble(&tracks);
+        let mut submitted_count = 0;
+        let mut remaining_tracks = vec![];
+
+        for (track, result) in tracks.into_iter().zip(results) {
+            match result {
+                Ok(_) => submitted_count += 1,
+                Err(error) if is_retryable(&error) => remaining_tracks.push(track),
+                Err(error) => Logger::error(
+                    tag,
+                    format!("Dropped queued scrobble {} - {}: {error:#}", track.artist.bright_blue(), track.title.bright_blue()),
+                    false,
+                ),
+            }
+        }
+
+        if submitted_count > 0 {
+            Logger::success(tag, format!("Successfully submitted {} queued scrobbles.", submitted_count.to_string().bright_blue()), false);
+        }
+
+        if !remaining_tracks.is_empty() {
+            self.entries.insert(backend.into(), remaining_tracks);
+        }
+
+        self.save();
+    }
+
+    // Retrying on every poll would only hit the same outage or rate limit again
+    pub fn is_flush_due(&mut self) -> bool {
+        if self.entries.values().all(Vec::is_empty) || self.flushed_at.is_some_and(|flushed_at| flushed_at.elapsed() < FLUSH_INTERVAL) {
+            return false;
+        }
+
+        self.flushed_at = Some(Instant::now());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_QUEUED_TRACKS, ScrobbleQueue};
+    use crate::scrobbler::Track;
+    use anyhow::{Error, anyhow};
+    use serde_json::from_str;
+    use std::{
+        collections::BTreeMap,
+        env::temp_dir,
+        fs::{read_to_string, remove_file},
+        process::id,
+    };
+
+    fn get_queue(name: &str) -> ScrobbleQueue {
+        ScrobbleQueue { path: temp_dir().join(format!("osu-scrobbler-{}-{name}.json", id())), entries: BTreeMap::new(), flushed_at: None }
+    }
+
+    fn get_track(listened_at: i64) -> Track {
+        Track {
+            artist: "LiSA".into(),
+            title: "Gurenge".into(),
+            album: None,
+            duration_secs: 90,
+            recording_mbid: None,
+            release_mbid: None,
+            artist_mbids: vec![],
+            artist_names: vec![],
+            listened_at,
+        }
+    }
+
+    fn get_listened_at(queue: &ScrobbleQueue, backend: &str) -> Vec<i64> {
+        queue.entries.get(backend).map(|tracks| tracks.iter().map(|track| track.listened_at).collect()).unwrap_or_default()
+    }
+
+    fn is_retryable(error: &Error) -> bool {
+        error.to_string() == "retryable"
+    }
+
+    #[test]
+    fn drop_oldest_tracks_when_full() {
+        let mut queue = get_queue("full");
+        queue.entries.insert("last_fm".into(), (0..MAX_QUEUED_TRACKS as i64).map(get_track).collect());
+
+        queue.push("last_fm", "Last.fm", get_track(MAX_QUEUED_TRACKS as i64));
+        queue.push("listenbrainz", "ListenBrainz", get_track(0));
+
+        let listened_at = get_listened_at(&queue, "last_fm");
+        assert_eq!(listened_at.len(), MAX_QUEUED_TRACKS);
+        assert_eq!(listened_at.first(), Some(&1));
+        assert_eq!(listened_at.last(), Some(&(MAX_QUEUED_TRACKS as i64)));
+        assert_eq!(get_listened_at(&queue, "listenbrainz"), [0]);
+
+        remove_file(&queue.path).ok();
+    }
+
+    #[test]
+    fn keep_only_retryable_tracks() {
+        let mut queue = get_queue("flush");
+        queue.entries.insert("last_fm".into(), (0..6).map(get_track).collect());
+        queue.entries.insert("listenbrainz".into(), vec![get_track(0)]);
+
+        let mut submitted = vec![];
+
+        queue.flush(
+            "last_fm",
+            "Last.fm",
+            |tracks| {
+                submitted = tracks.iter().map(|track| track.listened_at).collect();
+
+                tracks
+                    .iter()
+                    .map(|track| match track.listened_at % 3 {
+                        0 => Ok(()),
+                        1 => Err(anyhow!("retryable")),
+                        _ => Err(anyhow!("fatal")),
+                    })
+                    .collect()
+            },
+            is_retryable,
+        );
+
+        assert_eq!(submitted, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(get_listened_at(&queue, "last_fm"), [1, 4]);
+        assert_eq!(get_listened_at(&queue, "listenbrainz"), [0]);
+
+        let saved = from_str::<BTreeMap<String, Vec<Track>>>(&read_to_string(&queue.path).unwrap()).unwrap();
+        assert_eq!(saved.get("last_fm").map(Vec::len), Some(2));
+
+        queue.flush("last_fm", "Last.fm", |tracks| tracks.iter().map(|_| Ok(())).collect(), is_retryable);
+        assert!(!queue.entries.contains_key("last_fm"));
+
+        queue.flush("last_fm", "Last.fm", |_| panic!("An empty queue should not be submitted."), is_retryable);
+
+        remove_file(&queue.path).ok();
+    }
+
+    #[test]
+    fn throttle_flushes() {
+        let mut queue = get_queue("throttle");
+        assert!(!queue.is_flush_due());
+
+        queue.entries.insert("last_fm".into(), vec![get_track(0)]);
+        assert!(queue.is_flush_due());
+        assert!(!queue.is_flush_due());
+    }
+}
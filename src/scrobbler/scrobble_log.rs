@@ -0,0 +1,199 @@
+use crate::{
+    config::{Config, ScrobbleLogFormat, ScrobblerScrobbleLogConfig},
+    logger::RotatingFile,
+    scores::Score,
+    scrobbler::Track,
+};
+use anyhow::{Result, bail};
+use chrono::DateTime;
+use colored::Colorize;
+use serde_json::json;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+#[derive(Debug, Default)]
+pub struct ScrobbleLog {
+    file: Option<(ScrobblerScrobbleLogConfig, RotatingFile)>,
+}
+
+impl ScrobbleLog {
+    fn get_header(format: ScrobbleLogFormat) -> Option<String> {
+        match format {
+            ScrobbleLogFormat::Pipe | ScrobbleLogFormat::Jsonl => None,
+            ScrobbleLogFormat::Csv => Some("timestamp,score_id,artist,title,album,duration_secs".into()),
+            ScrobbleLogFormat::Rockbox => {
+                Some(format!("#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/osu-scrobbler {}", env!("CARGO_PKG_VERSION")))
+            },
+        }
+    }
+
+    fn is_format(format: ScrobbleLogFormat, first_line: &str) -> bool {
+        match format {
+            ScrobbleLogFormat::Pipe => {
+                !first_line.starts_with(['{', '#']) && Self::get_header(ScrobbleLogFormat::Csv).as_deref() != Some(first_line)
+            },
+            ScrobbleLogFormat::Csv => Self::get_header(format).as_deref() == Some(first_line),
+            ScrobbleLogFormat::Jsonl => first_line.starts_with('{'),
+            ScrobbleLogFormat::Rockbox => first_line.starts_with("#AUDIOSCROBBLER/"),
+        }
+    }
+
+    fn escape_csv(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) { format!("\"{}\"", field.replace('"', "\"\"")) } else { field.to_string() }
+    }
+
+    fn format_line(format: ScrobbleLogFormat, score: &Score, track: &Track) -> String {
+        let album = track.album.as_deref().unwrap_or_default();
+
+        match format {
+            ScrobbleLogFormat::Pipe => format!("{} | {} - {}", score.ended_at, track.artist, track.title),
+            ScrobbleLogFormat::Csv => [
+                score.ended_at.clone(),
+                score.id.to_string(),
+                Self::escape_csv(&track.artist),
+                Self::escape_csv(&track.title),
+                Self::escape_csv(album),
+                track.duration_secs.to_string(),
+            ]
+            .join(","),
+            ScrobbleLogFormat::Jsonl => json!({
+                "timestamp": score.ended_at,
+                "score_id": score.id,
+                "artist": track.artist,
+                "title": track.title,
+                "album": track.album,
+                "duration_secs": track.duration_secs,
+                "recording_mbid": track.recording_mbid,
+            })
+            .to_string(),
+            ScrobbleLogFormat::Rockbox => {
+                // Fields are tab-separated, so tabs inside them would shift the columns
+                let clean = |field: &str| field.replace('\t', " ");
+                let timestamp = DateTime::parse_from_rfc3339(&score.ended_at).map(|ended_at| ended_at.timestamp()).unwrap_or_default();
+
+                [
+                    clean(&track.artist),
+                    clean(album),
+                    clean(&track.title),
+                    String::new(),
+                    track.duration_secs.to_string(),
+                    "L".into(),
+                    timestamp.to_string(),
+                    track.recording_mbid.clone().unwrap_or_default(),
+                ]
+                .join("\t")
+            },
+        }
+    }
+
+    pub fn write(&mut self, config: &ScrobblerScrobbleLogConfig, score: &Score, track: &Track) -> Result<()> {
+        if self.file.as_ref().is_none_or(|(file_config, _)| file_config != config) {
+            self.file = None;
+
+            let path = Config::get_data_path(&config.path)?;
+            let first_line = File::open(&path).ok().and_then(|file| BufReader::new(file).lines().next()).and_then(|line| line.ok());
+            let mut file = RotatingFile::open(path, config.max_size_bytes, config.max_files, Self::get_header(config.format))?;
+
+            // Appending to a file written in another format would leave it unreadable by importers
+            if first_line.is_some_and(|first_line| !Self::is_format(config.format, &first_line)) {
+                if config.max_files == 0 {
+                    bail!(
+                        "Scrobble log {} was written in another format. Please move it or change the format back.",
+                        config.path.bright_blue()
+                    );
+                }
+
+                file.rotate()?;
+            }
+
+            self.file = Some((config.clone(), file));
+        }
+
+        let Some((_, file)) = self.file.as_mut() else { return Ok(()) };
+        file.write_line(&Self::format_line(config.format, score, track))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScrobbleLog;
+    use crate::{config::ScrobbleLogFormat, scores::Score, scrobbler::Track};
+    use serde_json::{from_value, json};
+
+    fn get_score() -> Score {
+        from_value(json!({
+            "id": 123,
+            "passed": true,
+            "mods": [],
+            "started_at": null,
+            "ended_at": "2026-01-01T00:00:00Z",
+            "beatmap": { "version": "Insane", "total_length": 90, "hit_length": 85 },
+            "beatmapset": {
+                "id": 1,
+                "artist": "LiSA",
+                "artist_unicode": "LiSA",
+                "title": "Gurenge",
+                "title_unicode": "紅蓮華",
+                "creator": "mapper",
+            },
+            "user": { "id": 1, "username": "player" },
+        }))
+        .unwrap()
+    }
+
+    fn get_track(artist: &str, title: &str, album: Option<&str>) -> Track {
+        Track {
+            artist: artist.into(),
+            title: title.into(),
+            album: album.map(String::from),
+            duration_secs: 90,
+            recording_mbid: Some("mbid".into()),
+            release_mbid: None,
+            artist_mbids: vec![],
+            artist_names: vec![],
+            listened_at: 1767225600,
+        }
+    }
+
+    #[test]
+    fn format_pipe() {
+        let line = ScrobbleLog::format_line(ScrobbleLogFormat::Pipe, &get_score(), &get_track("LiSA", "Gurenge", None));
+        assert_eq!(line, "2026-01-01T00:00:00Z | LiSA - Gurenge");
+    }
+
+    #[test]
+    fn format_csv() {
+        let track = get_track("Artist, With \"Quotes\"", "Line\nBreak", Some("Album"));
+        let line = ScrobbleLog::format_line(ScrobbleLogFormat::Csv, &get_score(), &track);
+        assert_eq!(line, "2026-01-01T00:00:00Z,123,\"Artist, With \"\"Quotes\"\"\",\"Line\nBreak\",Album,90");
+    }
+
+    #[test]
+    fn format_rockbox() {
+        let track = get_track("LiSA", "Gurenge\t(TV Size)", Some("Gurenge"));
+        let line = ScrobbleLog::format_line(ScrobbleLogFormat::Rockbox, &get_score(), &track);
+        assert_eq!(line, "LiSA\tGurenge\tGurenge (TV Size)\t\t90\tL\t1767225600\tmbid");
+    }
+
+    #[test]
+    fn detect_format() {
+        let csv_header = ScrobbleLog::get_header(ScrobbleLogFormat::Csv).unwrap();
+        let rockbox_header = ScrobbleLog::get_header(ScrobbleLogFormat::Rockbox).unwrap();
+        let rockbox_header = rockbox_header.lines().next().unwrap();
+        let pipe_line = "2026-01-01T00:00:00Z | LiSA - Gurenge";
+        let jsonl_line = r#"{"artist":"LiSA"}"#;
+
+        for (format, matching_line) in [
+            (ScrobbleLogFormat::Pipe, pipe_line),
+            (ScrobbleLogFormat::Csv, csv_header.as_str()),
+            (ScrobbleLogFormat::Jsonl, jsonl_line),
+            (ScrobbleLogFormat::Rockbox, rockbox_header),
+        ] {
+            for line in [pipe_line, csv_header.as_str(), jsonl_line, rockbox_header] {
+                assert_eq!(ScrobbleLog::is_format(format, line), line == matching_line, "{format:?} on {line:?}");
+            }
+        }
+    }
+}
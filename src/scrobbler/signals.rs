@@ -0,0 +1,24 @@
+use crate::scrobbler::ScrobblerAction;
+use anyhow::{Context, Result};
+use signal_hook::{
+    consts::{SIGUSR1, SIGUSR2},
+    iterator::Signals,
+};
+use std::{sync::mpsc::Sender, thread::spawn};
+
+// SIGUSR1 pauses scrobbling and SIGUSR2 resumes it
+pub fn listen(sender: Sender<ScrobblerAction>) -> Result<()> {
+    let mut signals = Signals::new([SIGUSR1, SIGUSR2]).context("Could not register signal handlers.")?;
+
+    spawn(move || {
+        for signal in signals.forever() {
+            let action = if signal == SIGUSR1 { ScrobblerAction::Pause(None) } else { ScrobblerAction::Resume };
+
+            if sender.send(action).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
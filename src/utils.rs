@@ -1,9 +1,23 @@
-use crate::{config::ScrobblerConfig, logger::Logger, scores::Score};
+use crate::{
+    config::{AlbumStrategy, CombinedRedirect, FeaturedArtistPolicy, MetadataPreference, Redirect, Script, ScrobblerConfig},
+    logger::Logger,
+    metrics::Metrics,
+    musicbrainz::{MusicBrainzCache, MusicBrainzRelease},
+    scores::Score,
+};
 use anyhow::{Result, bail};
 use colored::Colorize;
-use std::borrow::Cow;
+use regex::Regex;
+use std::{borrow::Cow, sync::LazyLock};
 use sysinfo::{ProcessRefreshKind, RefreshKind, System};
 
+const FEATURING_PATTERN: &str = r"feat\.?|ft\.?|featuring";
+
+static FEATURING_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!(r"(?i)\s+(?:{FEATURING_PATTERN})\s+")).unwrap());
+static FEATURED_TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(&format!(r"(?i)\b(?:{FEATURING_PATTERN})\s")).unwrap());
+static ANSI_ESCAPE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+static ARTIST_SEPARATOR_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\s+(?:x|×|&|vs\.?)\s+").unwrap());
+
 pub fn get_osu_pid() -> Option<u32> {
     let system = System::new_with_specifics(RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()));
 
@@ -14,25 +28,93 @@ pub fn get_osu_pid() -> Option<u32> {
         .map(|process| process.0.as_u32())
 }
 
+pub fn strip_ansi_codes(string: &str) -> Cow<'_, str> {
+    ANSI_ESCAPE_REGEX.replace_all(string, "")
+}
+
+pub fn get_metadata<'a>(romanized: &'a str, original: &'a str, preference: MetadataPreference, scripts: &[Script]) -> &'a str {
+    if original.trim().is_empty() {
+        return romanized;
+    }
+
+    match preference {
+        MetadataPreference::Romanized => romanized,
+        MetadataPreference::Original => original,
+        MetadataPreference::Auto => {
+            // Symbols, digits and whitespaces are shared across scripts, so only letters are checked
+            let is_readable =
+                original.chars().filter(|char| char.is_alphabetic()).all(|char| scripts.iter().any(|script| script.contains(char)));
+
+            if is_readable { original } else { romanized }
+        },
+    }
+}
+
 pub fn handle_redirects(score: &Score, artist: &str, title: &str, config: &ScrobblerConfig) -> (Option<String>, Option<String>) {
     let clean_extra_whitespaces = |string: Cow<'_, str>| string.trim().split(char::is_whitespace).collect::<Vec<&str>>().join(" ");
 
+    let mut preset_title = None;
+
+    for preset in &config.redirects.presets {
+        let haystack = preset_title.as_deref().unwrap_or(title);
+
+        if let Some(new_title) = preset.apply(haystack) {
+            Logger::debug(
+                "Scrobbler",
+                format!("Preset {} matched title {}. Applying...", preset.to_string().bright_blue(), haystack.bright_blue()),
+                false,
+            );
+
+            preset_title = Some(new_title);
+        }
+    }
+
+    let title = preset_title.as_deref().unwrap_or(title);
+    let mut combined_artist = None;
+    let mut combined_title = None;
+
+    for CombinedRedirect { regex, artist: artist_replacer, title: title_replacer } in &config.redirects.combined.regex_matches {
+        let haystack = format!("{} - {}", combined_artist.as_deref().unwrap_or(artist), combined_title.as_deref().unwrap_or(title));
+        let Some(captures) = regex.captures(&haystack) else { continue };
+
+        Logger::debug(
+            "Scrobbler",
+            format!("Regex {} matched track {}. Applying...", regex.to_string().bright_blue(), haystack.bright_blue()),
+            false,
+        );
+
+        if let Some(artist_replacer) = artist_replacer {
+            let mut new_artist = String::new();
+            captures.expand(artist_replacer, &mut new_artist);
+            combined_artist = Some(clean_extra_whitespaces(new_artist.into()));
+        }
+
+        if let Some(title_replacer) = title_replacer {
+            let mut new_title = String::new();
+            captures.expand(title_replacer, &mut new_title);
+            combined_title = Some(clean_extra_whitespaces(new_title.into()));
+        }
+    }
+
+    let artist = combined_artist.as_deref().unwrap_or(artist);
+    let title = combined_title.as_deref().unwrap_or(title);
+
     let artists = [score.beatmapset.artist.to_lowercase(), score.beatmapset.artist_unicode.to_lowercase()];
     let mut new_artist = None;
 
-    for (old, new) in &config.redirects.artists.equal_matches {
-        if artists.contains(old) && new != artist {
+    for Redirect { old, new, conditions } in &config.redirects.artists.equal_matches {
+        if artists.contains(old) && new != artist && conditions.is_met(score) {
             new_artist = Some(new.clone());
             break;
         }
     }
 
     if new_artist.is_none() {
-        for (regex, replacer) in &config.redirects.artists.regex_matches {
+        for Redirect { old: regex, new: replacer, conditions } in &config.redirects.artists.regex_matches {
             let haystack = new_artist.as_deref().unwrap_or(artist);
 
-            if regex.is_match(haystack) {
-                Logger::success(
+            if conditions.is_met(score) && regex.is_match(haystack) {
+                Logger::debug(
                     "Scrobbler",
                     format!("Regex {} matched artist {}. Applying...", regex.to_string().bright_blue(), haystack.bright_blue()),
                     false,
@@ -46,19 +128,19 @@ pub fn handle_redirects(score: &Score, artist: &str, title: &str, config: &Scrob
     let titles = [score.beatmapset.title.to_lowercase(), score.beatmapset.title_unicode.to_lowercase()];
     let mut new_title = None;
 
-    for (old, new) in &config.redirects.titles.equal_matches {
-        if titles.contains(old) && new != title {
+    for Redirect { old, new, conditions } in &config.redirects.titles.equal_matches {
+        if titles.contains(old) && new != title && conditions.is_met(score) {
             new_title = Some(new.clone());
             break;
         }
     }
 
     if new_title.is_none() {
-        for (regex, replacer) in &config.redirects.titles.regex_matches {
+        for Redirect { old: regex, new: replacer, conditions } in &config.redirects.titles.regex_matches {
             let haystack = new_title.as_deref().unwrap_or(title);
 
-            if regex.is_match(haystack) {
-                Logger::success(
+            if conditions.is_met(score) && regex.is_match(haystack) {
+                Logger::debug(
                     "Scrobbler",
                     format!("Regex {} matched title {}. Applying...", regex.to_string().bright_blue(), haystack.bright_blue()),
                     false,
@@ -69,10 +151,134 @@ pub fn handle_redirects(score: &Score, artist: &str, title: &str, config: &Scrob
         }
     }
 
-    (new_artist, new_title)
+    (new_artist.or(combined_artist), new_title.or(combined_title).or(preset_title))
+}
+
+pub fn handle_featured_artists(artist: &str, title: &str, config: &ScrobblerConfig) -> (Option<String>, Option<String>, Vec<String>) {
+    match config.featured_artists {
+        FeaturedArtistPolicy::Keep => (None, None, vec![]),
+        FeaturedArtistPolicy::MoveToTitle => {
+            let mut artists = FEATURING_REGEX.splitn(artist, 2);
+            let (Some(primary_artist), Some(featured_artist)) = (artists.next(), artists.next()) else { return (None, None, vec![]) };
+            let new_title = if FEATURED_TITLE_REGEX.is_match(title) { None } else { Some(format!("{title} (feat. {featured_artist})")) };
+            (Some(primary_artist.to_string()), new_title, vec![])
+        },
+        FeaturedArtistPolicy::Split => {
+            // Separators such as "&" are also part of single artist names like "Simon & Garfunkel", so they are only split on when enabled
+            let artist_names = FEATURING_REGEX
+                .split(artist)
+                .flat_map(
+                    |artist| {
+                        if config.split_artist_separators { ARTIST_SEPARATOR_REGEX.split(artist).collect() } else { vec![artist] }
+                    },
+                )
+                .map(|artist| artist.to_string())
+                .collect::<Vec<String>>();
+            if artist_names.len() < 2 { (None, None, vec![]) } else { (Some(artist_names[0].clone()), None, artist_names) }
+        },
+    }
+}
+
+pub fn handle_album_redirects(score: &Score, artist: &str, title: &str, album: Option<&str>, config: &ScrobblerConfig) -> Option<String> {
+    let clean_extra_whitespaces = |string: Cow<'_, str>| string.trim().split(char::is_whitespace).collect::<Vec<&str>>().join(" ");
+    let config = &config.redirects.albums;
+
+    let artists = [artist.to_lowercase(), score.beatmapset.artist.to_lowercase(), score.beatmapset.artist_unicode.to_lowercase()];
+    let titles = [title.to_lowercase(), score.beatmapset.title.to_lowercase(), score.beatmapset.title_unicode.to_lowercase()];
+
+    for (old_artist, old_title, new) in &config.track_matches {
+        if artists.contains(old_artist) && titles.contains(old_title) && album != Some(new.as_str()) {
+            return Some(new.clone());
+        }
+    }
+
+    let album = album?;
+
+    for Redirect { old, new, conditions } in &config.equal_matches {
+        if album.to_lowercase() == *old && new != album && conditions.is_met(score) {
+            return Some(new.clone());
+        }
+    }
+
+    let mut new_album = None;
+
+    for Redirect { old: regex, new: replacer, conditions } in &config.regex_matches {
+        let haystack = new_album.as_deref().unwrap_or(album);
+
+        if conditions.is_met(score) && regex.is_match(haystack) {
+            Logger::debug(
+                "Scrobbler",
+                format!("Regex {} matched album {}. Applying...", regex.to_string().bright_blue(), haystack.bright_blue()),
+                false,
+            );
+
+            new_album = Some(clean_extra_whitespaces(regex.replace_all(haystack, replacer)));
+        }
+    }
+
+    new_album
+}
+
+pub fn get_album(
+    score: &Score,
+    artist: &str,
+    title: &str,
+    config: &ScrobblerConfig,
+    musicbrainz_cache: &mut MusicBrainzCache,
+) -> (Option<String>, Option<MusicBrainzRelease>) {
+    if let Some(album) = config.album.beatmapsets.get(&score.beatmapset.id.to_string()) {
+        return (Some(album.clone()), None);
+    }
+
+    for strategy in &config.album.strategies {
+        match strategy {
+            AlbumStrategy::MusicBrainz => {
+                if config.fetch_album_names
+                    && let Some(release) = musicbrainz_cache.get_release(score, artist, title, &config.musicbrainz)
+                {
+                    return (Some(release.title.clone()), Some(release));
+                }
+            },
+            AlbumStrategy::Source => {
+                let source = score.beatmapset.source.trim();
+
+                if !source.is_empty() {
+                    let album =
+                        config.album.source_template.replace("{source}", source).replace("{artist}", artist).replace("{title}", title);
+                    return (Some(album), None);
+                }
+            },
+            AlbumStrategy::Fixed => {
+                if let Some(album) = config.album.fixed.as_ref() {
+                    return (Some(album.clone()), None);
+                }
+            },
+        }
+    }
+
+    (None, None)
 }
 
 pub fn validate_scrobble(score: &Score, config: &ScrobblerConfig) -> Result<()> {
+    if let Err(error) = validate_blacklist(score, config) {
+        Metrics::record_skip("blacklist");
+        return Err(error);
+    }
+
+    if score.beatmap.total_length < config.min_beatmap_length_secs {
+        Metrics::record_skip("length");
+
+        bail!(
+            "Beatmap's total length ({}) is less than the configured minimum length ({}).",
+            format!("{}s", score.beatmap.total_length).bright_blue(),
+            format!("{}s", config.min_beatmap_length_secs).bright_blue(),
+        );
+    }
+
+    Ok(())
+}
+
+fn validate_blacklist(score: &Score, config: &ScrobblerConfig) -> Result<()> {
     let (artist_romanized, artist_original) = (score.beatmapset.artist.to_lowercase(), score.beatmapset.artist_unicode.to_lowercase());
 
     if config.blacklist.artists.equal_matches.contains(&artist_romanized)
@@ -117,13 +323,61 @@ pub fn validate_scrobble(score: &Score, config: &ScrobblerConfig) -> Result<()>
         bail!("Beatmap difficulty matches a blacklisted regex ({}).", regex.as_str().bright_red());
     }
 
-    if score.beatmap.total_length < config.min_beatmap_length_secs {
-        bail!(
-            "Beatmap's total length ({}) is less than the configured minimum length ({}).",
-            format!("{}s", score.beatmap.total_length).bright_blue(),
-            format!("{}s", config.min_beatmap_length_secs).bright_blue(),
-        );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::handle_featured_artists;
+    use crate::config::ScrobblerConfig;
+
+    fn get_config(featured_artists: &str, split_artist_separators: bool) -> ScrobblerConfig {
+        toml::from_str(&format!(
+            "user_id = 1\nfeatured_artists = \"{featured_artists}\"\nsplit_artist_separators = {split_artist_separators}",
+        ))
+        .unwrap()
     }
 
-    Ok(())
+    #[test]
+    fn move_featured_artist_to_title() {
+        let config = get_config("move_to_title", false);
+
+        for (artist, title, expected_artist, expected_title) in [
+            ("Camellia feat. Nanahira", "Bassdrop Freaks", Some("Camellia"), Some("Bassdrop Freaks (feat. Nanahira)")),
+            ("Camellia ft Nanahira", "Bassdrop Freaks", Some("Camellia"), Some("Bassdrop Freaks (feat. Nanahira)")),
+            ("Camellia ft Nanahira", "Bassdrop Freaks ft Nanahira", Some("Camellia"), None),
+            ("Camellia ft. Nanahira", "Bassdrop Freaks (ft. Nanahira)", Some("Camellia"), None),
+            ("Camellia", "Bassdrop Freaks", None, None),
+        ] {
+            let (new_artist, new_title, _) = handle_featured_artists(artist, title, &config);
+            assert_eq!((new_artist.as_deref(), new_title.as_deref()), (expected_artist, expected_title), "{artist:?} - {title:?}");
+        }
+    }
+
+    #[test]
+    fn split_featured_artists_only() {
+        let config = get_config("split", false);
+
+        for (artist, expected) in [
+            ("Simon & Garfunkel", vec![]),
+            ("Camellia vs. Akira Complex", vec![]),
+            ("Camellia feat. Nanahira", vec!["Camellia", "Nanahira"]),
+            ("Simon & Garfunkel ft. Someone", vec!["Simon & Garfunkel", "Someone"]),
+        ] {
+            assert_eq!(handle_featured_artists(artist, "Title", &config).2, expected, "{artist:?}");
+        }
+    }
+
+    #[test]
+    fn split_artist_separators() {
+        let config = get_config("split", true);
+
+        for (artist, expected) in [
+            ("Camellia vs. Akira Complex", vec!["Camellia", "Akira Complex"]),
+            ("Kobaryo x Camellia feat. Nanahira", vec!["Kobaryo", "Camellia", "Nanahira"]),
+            ("xi", vec![]),
+        ] {
+            assert_eq!(handle_featured_artists(artist, "Title", &config).2, expected, "{artist:?}");
+        }
+    }
 }